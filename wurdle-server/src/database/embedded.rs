@@ -0,0 +1,14 @@
+use super::{parser, traits};
+
+pub const ANSWERS: &str = include_str!("../../words/answers.txt");
+pub const ALLOWED_WORDS: &str = include_str!("../../words/allowed.txt");
+
+// The lists are the last resort when downloads fail, a binary without them could not start
+const _: () = assert!(
+    !ANSWERS.is_empty() && !ALLOWED_WORDS.is_empty(),
+    "the embedded word lists are empty, download them with `make words`"
+);
+
+pub fn get_words_list<T: FromIterator<String>>(name: &str, body: &str) -> Result<T, traits::Error> {
+    parser::parse_words_list(&format!("embedded:{}", name), body)
+}
@@ -0,0 +1,49 @@
+use super::{parser, traits};
+use std::path::Path;
+use tokio::fs;
+
+// Read without blocking the runtime, the lists are loaded at the same time as the downloads
+async fn read_file(path: &Path) -> Result<String, traits::Error> {
+    fs::read_to_string(path)
+        .await
+        .map_err(|source| traits::Error::File {
+            path: path.display().to_string(),
+            source,
+        })
+}
+
+pub async fn get_words_list<T: FromIterator<String>>(path: &Path) -> Result<T, traits::Error> {
+    let body = read_file(path).await?;
+    parser::parse_words_list(&path.display().to_string(), &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Unique to this test run
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("wurdle-{}-{}", std::process::id(), name))
+    }
+
+    #[tokio::test]
+    async fn lists_are_read_from_files() {
+        let path = temp_path("answers.txt");
+        std::fs::write(&path, "# answers\ncrane\r\nSloth\n").unwrap();
+        let words: Result<Vec<String>, _> = get_words_list(&path).await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(words.unwrap(), ["crane", "sloth"]);
+    }
+
+    #[tokio::test]
+    async fn missing_files_are_reported() {
+        let path = temp_path("missing.txt");
+        let result = get_words_list::<Vec<String>>(&path).await;
+        assert!(matches!(
+            result,
+            Err(traits::Error::File { path: p, source })
+                if p == path.display().to_string() && source.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+}
@@ -1,121 +1,645 @@
+use super::ids::WordIds;
+use super::languages::Language;
+use super::parser;
+use super::pools::{self, Pool};
+use super::source;
 use super::traits;
-use rand::rngs::StdRng;
-use rand::seq::SliceRandom;
-use rand::SeedableRng;
-use std::collections::{HashMap, HashSet};
-use std::io::Read;
-
-const RESTRICTED_LENGTH: usize = 500;
-static SAMPLING_SEED: &str = "sample me baby";
-static ALLOWED_WORDS: &str = "https://gist.githubusercontent.com/cfreshman/40608e78e83eb4e1d60b285eb7e9732f/raw/2f51b4f2bb96c02e1dee37808b2eed4ef23a3150/wordle-nyt-allowed-guesses.txt";
-static ANSWERS: &str = "https://gist.githubusercontent.com/cfreshman/a7b776506c73284511034e63af1017ee/raw/845966807347a7b857d53294525263408be967ce/wordle-nyt-answers-alphabetical.txt";
-
-fn do_request(url: &str) -> Result<String, traits::Error> {
-    let mut res = reqwest::blocking::get(url)?;
-    let mut body = String::new();
-    res.read_to_string(&mut body)?;
-    Ok(body)
+use crate::game::letters;
+use log::{info, warn};
+use ring::digest;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+pub static ALLOWED_WORDS: &str = "https://gist.githubusercontent.com/cfreshman/40608e78e83eb4e1d60b285eb7e9732f/raw/2f51b4f2bb96c02e1dee37808b2eed4ef23a3150/wordle-nyt-allowed-guesses.txt";
+pub static ANSWERS: &str = "https://gist.githubusercontent.com/cfreshman/a7b776506c73284511034e63af1017ee/raw/845966807347a7b857d53294525263408be967ce/wordle-nyt-answers-alphabetical.txt";
+
+// Downloads word lists, a single client is shared by all downloads
+#[derive(Clone)]
+pub struct Fetcher {
+    client: reqwest::Client,
+    retries: u32,
+    backoff: Duration,
 }
 
-fn get_words_list<T: FromIterator<String>>(url: &str) -> Result<T, traits::Error> {
-    let body = do_request(url)?;
-    Ok(body.split('\n').map(str::to_string).collect::<T>())
+impl Fetcher {
+    // `timeout` applies to each attempt, `backoff` is the delay before the first retry and
+    // doubles after each one
+    pub fn new(timeout: Duration, retries: u32, backoff: Duration) -> Result<Self, traits::Error> {
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+            retries,
+            backoff,
+        })
+    }
+
+    async fn get(&self, url: &str) -> Result<String, reqwest::Error> {
+        self.client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    }
+
+    // Only connection errors, timeouts and server errors are worth retrying, other errors would
+    // happen again
+    fn is_transient(err: &reqwest::Error) -> bool {
+        err.is_connect()
+            || err.is_request()
+            || err.is_body()
+            || err.is_timeout()
+            || err.status().is_some_and(|status| status.is_server_error())
+    }
+
+    async fn get_with_retries(&self, url: &str) -> Result<String, traits::Error> {
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        loop {
+            match self.get(url).await {
+                Ok(body) => return Ok(body),
+                Err(err) if attempt < self.retries && Self::is_transient(&err) => {
+                    attempt += 1;
+                    warn!(
+                        "could not download {} (attempt {} of {}), retrying in {:?}: {}",
+                        url,
+                        attempt,
+                        self.retries + 1,
+                        delay,
+                        err
+                    );
+                    tokio::time::delay_for(delay).await;
+                    delay *= 2;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    pub async fn get_words_list<T: FromIterator<String>>(
+        &self,
+        url: &str,
+    ) -> Result<T, traits::Error> {
+        let body = self.get_with_retries(url).await?;
+        parser::parse_words_list(url, &body)
+    }
 }
 
+// Answers sharing the same length
 #[derive(Clone)]
-pub struct Database {
+struct Answers {
+    version: u32,
     words: Vec<String>,
-    restricted_words: Vec<String>,
+    // Id of each word in `words`
+    ids: Vec<String>,
+    // Named subsets of `words`, by name
+    pools: HashMap<String, Vec<String>>,
     words_lookup: HashMap<String, usize>,
+    ids_lookup: HashMap<String, usize>,
+}
+
+#[derive(Clone)]
+pub struct Database {
+    answers: BTreeMap<u8, Answers>,
+    // Name and description of every pool, in order
+    pools: Vec<(String, String)>,
     guesses: HashSet<String>,
+    fold_accents: bool,
 }
 
-fn get_rng() -> StdRng {
-    let mut seed: [u8; 32] = [0; 32];
-    seed[..SAMPLING_SEED.len()].copy_from_slice(SAMPLING_SEED.as_bytes());
-    StdRng::from_seed(seed)
+fn word_length(word: &str) -> Option<u8> {
+    u8::try_from(letters::length(word)).ok()
 }
 
-impl Database {
-    pub fn new() -> Result<Self, traits::Error> {
-        let mut words: Vec<String> = get_words_list(ANSWERS)?;
-        words.sort();
+// Changes whenever a word is added or removed, which changes the position of words in the list
+fn list_version(words: &[String]) -> u32 {
+    let hash = digest::digest(&digest::SHA256, words.join("\n").as_bytes());
+    let mut version = [0; 4];
+    version.copy_from_slice(&hash.as_ref()[..4]);
+    u32::from_be_bytes(version)
+}
+
+impl Answers {
+    fn new(words: Vec<String>, ids: &WordIds) -> Self {
         let words_lookup = words
             .iter()
             .enumerate()
             .map(|(pos, word)| (word.clone(), pos))
             .collect();
+        let ids = words.iter().map(|word| ids.id(word)).collect::<Vec<_>>();
+        let ids_lookup = ids
+            .iter()
+            .enumerate()
+            .map(|(pos, id)| (id.clone(), pos))
+            .collect();
 
-        let mut restricted_words: Vec<String> = words.iter().map(|word| word.clone()).collect();
-        let mut rng = get_rng();
-        restricted_words.shuffle(&mut rng);
-        restricted_words.truncate(RESTRICTED_LENGTH);
-        restricted_words.sort();
-
-        Ok(Self {
+        Self {
+            version: list_version(&words),
+            ids,
+            pools: HashMap::new(),
             words,
-            restricted_words,
             words_lookup,
-            guesses: get_words_list(ALLOWED_WORDS)?,
+            ids_lookup,
+        }
+    }
+
+    fn list(&self, word_length: u8, pool: &str) -> Result<&Vec<String>, traits::Error> {
+        match pool {
+            pools::FULL_POOL => Ok(&self.words),
+            pool => self
+                .pools
+                .get(pool)
+                .filter(|words| !words.is_empty())
+                .ok_or_else(|| traits::Error::UnknownPool {
+                    pool: pool.to_string(),
+                    length: word_length,
+                }),
+        }
+    }
+}
+
+impl Database {
+    // Pools are built in order, a pool replaces any previous pool with the same name
+    pub async fn new(
+        language: &Language,
+        pools: &[Pool],
+        ids: &WordIds,
+        fetcher: &Fetcher,
+    ) -> Result<Self, traits::Error> {
+        // Both lists are downloaded at the same time
+        let (answers, allowed) = futures::try_join!(
+            source::first_words::<Vec<String>>(&language.answers, fetcher),
+            source::first_words::<Vec<String>>(&language.allowed, fetcher),
+        )?;
+
+        let fold = |word: String| match language.fold_accents {
+            true => letters::normalize(&word, true),
+            false => word,
+        };
+
+        let mut by_length: BTreeMap<u8, Vec<String>> = BTreeMap::new();
+        for word in answers {
+            let word = fold(word);
+            if let Some(length) = word_length(&word) {
+                by_length.entry(length).or_default().push(word);
+            }
+        }
+        // Folding accents can make words identical
+        for words in by_length.values_mut() {
+            words.sort();
+            words.dedup();
+        }
+
+        let mut answers = by_length
+            .into_iter()
+            .map(|(length, words)| (length, Answers::new(words, ids)))
+            .collect::<BTreeMap<_, _>>();
+        for (length, words) in answers.iter() {
+            info!(
+                "{} answers of {} letters in {} (version {:08x})",
+                words.words.len(),
+                length,
+                language.code,
+                words.version
+            );
+        }
+
+        let mut names = vec![(
+            pools::FULL_POOL.to_string(),
+            pools::FULL_DESCRIPTION.to_string(),
+        )];
+        for pool in pools {
+            if pool.name() == pools::FULL_POOL {
+                return Err(traits::Error::InvalidPool {
+                    entry: pool.name().to_string(),
+                });
+            }
+            let lists = answers
+                .values()
+                .map(|answers| answers.words.clone())
+                .collect::<Vec<_>>();
+            for ((length, answers), words) in
+                answers.iter_mut().zip(pool.select(&lists, fetcher).await?)
+            {
+                info!(
+                    "{} answers of {} letters in {} in pool {}",
+                    words.len(),
+                    length,
+                    language.code,
+                    pool.name()
+                );
+                answers.pools.insert(pool.name().to_string(), words);
+            }
+            names.retain(|(name, _)| name != pool.name());
+            names.push((pool.name().to_string(), pool.description().to_string()));
+        }
+
+        Ok(Self {
+            answers,
+            pools: names,
+            guesses: allowed.into_iter().map(fold).collect(),
+            fold_accents: language.fold_accents,
         })
     }
+
+    fn answers(&self, word_length: u8) -> Result<&Answers, traits::Error> {
+        self.answers
+            .get(&word_length)
+            .ok_or(traits::Error::UnsupportedLength {
+                length: word_length,
+            })
+    }
 }
 
 impl traits::Database for Database {
     fn word_for_id(&self, id: &str) -> Result<traits::Word, traits::Error> {
-        let index = id
-            .parse::<usize>()
-            .map_err(|_e| traits::Error::InvalidID { id: id.to_string() })?;
-        self.word_for_index(index)
+        // Ids are derived from the word, they stay valid as long as the word is in the list
+        let (length, index) = self
+            .answers
+            .iter()
+            .find_map(|(length, answers)| Some((*length, *answers.ids_lookup.get(id)?)))
+            .ok_or_else(|| traits::Error::InvalidID { id: id.to_string() })?;
+        self.word_for_index(length, pools::FULL_POOL, index)
     }
 
-    fn word_exists(&self, word: &str) -> Result<traits::Word, traits::Error> {
-        match self.words_lookup.get(&word.to_string()) {
-            Some(index) => Ok(traits::Word {
-                word_id: index.to_string(),
-                word: word.to_string(),
-            }),
-            None => Err(traits::Error::MissingWord {
-                word: word.to_string(),
+    fn word_for_session(
+        &self,
+        word_length: u8,
+        id: &str,
+        version: Option<u32>,
+    ) -> Result<traits::Word, traits::Error> {
+        let answers = self.answers(word_length)?;
+        match version {
+            Some(version) => self.word_for_id(id).map_err(|err| match err {
+                traits::Error::InvalidID { .. } if version != answers.version => {
+                    traits::Error::StaleList {
+                        version,
+                        current: answers.version,
+                    }
+                }
+                err => err,
             }),
+            // Sessions created before lists were versioned store the position of the word in
+            // the list instead, which used to start with an empty line
+            None => {
+                let index = id
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| index.checked_sub(1))
+                    .ok_or_else(|| traits::Error::InvalidID { id: id.to_string() })?;
+                self.word_for_index(word_length, pools::FULL_POOL, index)
+            }
         }
     }
 
-    fn word_length(&self, restricted: bool) -> Result<usize, traits::Error> {
-        Ok(match restricted {
-            true => &self.restricted_words,
-            false => &self.words,
-        }
-        .len())
+    fn word_exists(&self, word: &str) -> Result<traits::Word, traits::Error> {
+        let word = letters::clean(word, self.fold_accents);
+        let (length, index) = word_length(&word)
+            .and_then(|length| Some((length, *self.answers.get(&length)?.words_lookup.get(&word)?)))
+            .ok_or(traits::Error::MissingWord { word })?;
+        self.word_for_index(length, pools::FULL_POOL, index)
+    }
+
+    fn word_count(&self, word_length: u8, pool: &str) -> Result<usize, traits::Error> {
+        Ok(self.answers(word_length)?.list(word_length, pool)?.len())
     }
 
-    fn word_for_index(&self, index: usize) -> Result<traits::Word, traits::Error> {
-        let len = self.words.len();
+    fn word_for_index(
+        &self,
+        word_length: u8,
+        pool: &str,
+        index: usize,
+    ) -> Result<traits::Word, traits::Error> {
+        let answers = self.answers(word_length)?;
+        let words = answers.list(word_length, pool)?;
+        let len = words.len();
         if len <= index {
             return Err(traits::Error::OutOfBounds {
                 index,
                 maximum: len,
             });
         }
+        // Pools are subsets of the full list, which is where ids come from
+        let word = &words[index];
+        let position = answers.words_lookup[word];
         Ok(traits::Word {
-            word_id: index.to_string(),
-            word: self.words[index].clone(),
+            word_id: answers.ids[position].clone(),
+            word: word.clone(),
+            version: answers.version,
         })
     }
 
     fn guess_exists(&self, word: &str) -> Result<bool, traits::Error> {
-        Ok(self.guesses.contains(word) || self.words_lookup.contains_key(word))
+        Ok(self
+            .guesses
+            .contains(&letters::clean(word, self.fold_accents))
+            || self.word_exists(word).is_ok())
+    }
+
+    fn allowed_words(&self, word_length: u8) -> Result<Vec<String>, traits::Error> {
+        // Only lengths with answers can be played
+        self.answers(word_length)?;
+        Ok(Vec::from_iter(
+            self.guesses
+                .iter()
+                .filter(|word| letters::length(word) == word_length as usize)
+                .cloned(),
+        ))
+    }
+
+    fn answer_words(&self, word_length: u8, pool: &str) -> Result<Vec<String>, traits::Error> {
+        Ok(self.answers(word_length)?.list(word_length, pool)?.to_vec())
+    }
+
+    fn pools(&self) -> Result<Vec<traits::Pool>, traits::Error> {
+        Ok(self
+            .pools
+            .iter()
+            .map(|(name, description)| traits::Pool {
+                name: name.clone(),
+                description: description.clone(),
+                word_counts: self
+                    .answers
+                    .iter()
+                    .filter_map(|(length, answers)| {
+                        answers
+                            .list(*length, name)
+                            .ok()
+                            .map(|words| (*length, words.len()))
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    fn folds_accents(&self) -> bool {
+        self.fold_accents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::embedded;
+    use crate::database::traits::Database as _;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server};
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    #[derive(Clone)]
+    struct Reply {
+        status: u16,
+        body: &'static str,
+        delay: Duration,
+    }
+
+    fn reply(status: u16, body: &'static str) -> Reply {
+        Reply {
+            status,
+            body,
+            delay: Duration::ZERO,
+        }
     }
 
-    fn allowed_words(&self) -> Result<Vec<String>, traits::Error> {
-        return Ok(Vec::from_iter(self.guesses.clone()));
+    // Serves the replies in order (the last one is repeated), returns its URL and how many
+    // requests it received
+    fn stub(replies: Vec<Reply>) -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let make_service = make_service_fn(move |_connection| {
+            let replies = replies.clone();
+            let counter = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |_request| {
+                    let hit = counter.fetch_add(1, Ordering::SeqCst);
+                    let reply = replies[hit.min(replies.len() - 1)].clone();
+                    async move {
+                        tokio::time::delay_for(reply.delay).await;
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(reply.status)
+                                .body(Body::from(reply.body))
+                                .unwrap(),
+                        )
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}/words.txt", server.local_addr());
+        tokio::spawn(server);
+        (url, hits)
+    }
+
+    fn fetcher(timeout: Duration, retries: u32) -> Fetcher {
+        Fetcher::new(timeout, retries, Duration::from_millis(20)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_with_backoff() {
+        let (url, hits) = stub(vec![
+            reply(500, ""),
+            reply(503, ""),
+            reply(200, "crane\nsloth\n"),
+        ]);
+        let start = Instant::now();
+        let words: Vec<String> = fetcher(Duration::from_secs(5), 3)
+            .get_words_list(&url)
+            .await
+            .unwrap();
+        assert_eq!(words, ["crane", "sloth"]);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        // 20ms then 40ms
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[tokio::test]
+    async fn retries_are_limited() {
+        let (url, hits) = stub(vec![reply(502, "")]);
+        let result = fetcher(Duration::from_secs(5), 2)
+            .get_words_list::<Vec<String>>(&url)
+            .await;
+        assert!(matches!(result, Err(traits::Error::Http(err)) if err.status().is_some()));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let (url, hits) = stub(vec![reply(404, ""), reply(200, "crane\n")]);
+        let result = fetcher(Duration::from_secs(5), 3)
+            .get_words_list::<Vec<String>>(&url)
+            .await;
+        assert!(matches!(result, Err(traits::Error::Http(err)) if err.status().is_some()));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_retried() {
+        let slow = Reply {
+            delay: Duration::from_secs(2),
+            ..reply(200, "slow\n")
+        };
+        let (url, hits) = stub(vec![slow.clone(), reply(200, "crane\n")]);
+        let words: Vec<String> = fetcher(Duration::from_millis(200), 1)
+            .get_words_list(&url)
+            .await
+            .unwrap();
+        assert_eq!(words, ["crane"]);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        let (url, _hits) = stub(vec![slow]);
+        let result = fetcher(Duration::from_millis(200), 0)
+            .get_words_list::<Vec<String>>(&url)
+            .await;
+        assert!(matches!(result, Err(traits::Error::Http(err)) if err.is_timeout()));
+    }
+
+    #[tokio::test]
+    async fn every_failure_is_reported() {
+        let (missing, _hits) = stub(vec![reply(404, "")]);
+        let (invalid, _hits) = stub(vec![reply(200, "crane\ncr4ne\n")]);
+        // Nothing listens on a port which was just released
+        let closed = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}/words.txt", listener.local_addr().unwrap())
+        };
+        let sources = [
+            source::Source::Http {
+                url: missing.clone(),
+            },
+            source::Source::Http {
+                url: invalid.clone(),
+            },
+            source::Source::Http {
+                url: closed.clone(),
+            },
+            source::Source::Embedded {
+                name: "answers",
+                body: "# nothing\n",
+            },
+        ];
+        let result =
+            source::first_words::<Vec<String>>(&sources, &fetcher(Duration::from_secs(5), 1)).await;
+        let failures = match result {
+            Err(traits::Error::Unavailable { failures }) => failures,
+            result => panic!("unexpected result: {:?}", result),
+        };
+        assert_eq!(failures.len(), 4);
+        assert!(failures[0].starts_with(&missing) && failures[0].contains("404"));
+        assert!(failures[1].starts_with(&invalid) && failures[1].contains("\"cr4ne\" at line 2"));
+        assert!(failures[2].starts_with(&closed));
+        assert_eq!(failures[3], "empty word list: embedded:answers");
+    }
+
+    #[tokio::test]
+    async fn embedded_lists_are_used_after_a_failing_download() {
+        let (url, _hits) = stub(vec![reply(503, "")]);
+        for (name, body) in [
+            ("answers", embedded::ANSWERS),
+            ("allowed", embedded::ALLOWED_WORDS),
+        ] {
+            let sources = [
+                source::Source::Http { url: url.clone() },
+                source::Source::Embedded { name, body },
+            ];
+            let words: Vec<String> =
+                source::first_words(&sources, &fetcher(Duration::from_secs(5), 1))
+                    .await
+                    .unwrap();
+            assert!(!words.is_empty());
+            assert_eq!(
+                words,
+                embedded::get_words_list::<Vec<String>>(name, body).unwrap()
+            );
+        }
+    }
+
+    async fn database(
+        answers: &'static str,
+        allowed: &'static str,
+        fold_accents: bool,
+    ) -> Database {
+        let language = Language {
+            code: "fr".to_string(),
+            answers: vec![source::Source::Embedded {
+                name: "answers",
+                body: answers,
+            }],
+            allowed: vec![source::Source::Embedded {
+                name: "allowed",
+                body: allowed,
+            }],
+            fold_accents,
+        };
+        let ids = WordIds::new(b"test secret").unwrap();
+        Database::new(&language, &[], &ids, &fetcher(Duration::from_secs(1), 0))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn accents_are_folded() {
+        let db = database("élan\nelan\ncafé\nbâton\n", "crème\n", true).await;
+        assert!(db.folds_accents());
+        // Words which become identical are only listed once
+        assert_eq!(
+            db.answer_words(4, pools::FULL_POOL).unwrap(),
+            ["cafe", "elan"]
+        );
+        assert_eq!(db.answer_words(5, pools::FULL_POOL).unwrap(), ["baton"]);
+        assert_eq!(db.allowed_words(5).unwrap(), ["creme"]);
+
+        let elan = db.word_exists("elan").unwrap();
+        for word in ["élan", "Élan", " ELAN "] {
+            let word = db.word_exists(word).unwrap();
+            assert_eq!(word.word, "elan");
+            assert_eq!(word.word_id, elan.word_id);
+        }
+        for guess in ["crème", "creme", "CRÈME", "bâton", "baton", "cafe"] {
+            assert!(db.guess_exists(guess).unwrap(), "{}", guess);
+        }
+        assert!(!db.guess_exists("crâne").unwrap());
+
+        // Without folding, accented letters are different letters
+        let db = database("élan\nelan\ncafé\nbâton\n", "crème\n", false).await;
+        assert!(!db.folds_accents());
+        assert_eq!(
+            db.answer_words(4, pools::FULL_POOL).unwrap(),
+            ["café", "elan", "élan"]
+        );
+        assert_ne!(
+            db.word_exists("élan").unwrap().word_id,
+            db.word_exists("elan").unwrap().word_id
+        );
+        assert!(db.word_exists("cafe").is_err());
+        assert!(!db.guess_exists("creme").unwrap());
     }
 
-    fn answer_words(&self, restricted: bool) -> Result<Vec<String>, traits::Error> {
-        Ok(match restricted {
-            true => &self.restricted_words,
-            false => &self.words,
+    #[tokio::test]
+    async fn versions_hash_the_folded_words() {
+        let version = |db: &Database, length| {
+            db.word_for_index(length, pools::FULL_POOL, 0)
+                .unwrap()
+                .version
+        };
+        let folded = database("élan\ncafé\nbâton\n", "crème\n", true).await;
+        let plain = database("elan\ncafe\nbaton\n", "crème\n", false).await;
+        let accented = database("élan\ncafé\nbâton\n", "crème\n", false).await;
+        for length in [4, 5] {
+            assert_eq!(version(&folded, length), version(&plain, length));
+            assert_ne!(version(&folded, length), version(&accented, length));
         }
-        .to_vec())
+        // Each length has its own version, which only changes with its own words
+        assert_ne!(version(&plain, 4), version(&plain, 5));
+        let more = database("elan\ncafe\nbaton\nbelle\n", "crème\n", false).await;
+        assert_eq!(version(&more, 4), version(&plain, 4));
+        assert_ne!(version(&more, 5), version(&plain, 5));
+        assert_eq!(
+            version(&plain, 4),
+            list_version(&["cafe".to_string(), "elan".to_string()])
+        );
     }
 }
@@ -0,0 +1,34 @@
+use super::traits;
+use ring::{hkdf, hmac};
+
+static IDS_SALT: &[u8] = b"wurdle";
+static IDS_INFO: &[u8] = b"word ids";
+// 64 bits are plenty to avoid collisions between words
+const ID_LENGTH: usize = 8;
+
+// Word ids are a keyed hash of the word, they are stable but reveal nothing about the word (nor
+// its position in the list) without the key
+#[derive(Clone)]
+pub struct WordIds {
+    // Never rotated, ids given to clients must stay valid
+    key: hmac::Key,
+}
+
+impl WordIds {
+    pub fn new(secret: &[u8]) -> Result<Self, traits::Error> {
+        if secret.is_empty() {
+            return Err(traits::Error::NoKey);
+        }
+        // Derived so that the same secret can be used to sign sessions
+        let key = hkdf::Salt::new(hkdf::HKDF_SHA256, IDS_SALT)
+            .extract(secret)
+            .expand(&[IDS_INFO], hmac::HMAC_SHA256)?
+            .into();
+        Ok(Self { key })
+    }
+
+    pub fn id(&self, word: &str) -> String {
+        let tag = hmac::sign(&self.key, word.as_bytes());
+        base64::encode_config(&tag.as_ref()[..ID_LENGTH], base64::URL_SAFE_NO_PAD)
+    }
+}
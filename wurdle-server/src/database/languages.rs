@@ -0,0 +1,125 @@
+use super::source::Source;
+use super::traits;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+// Uses the lists given by `ANSWERS_FILE` and `ALLOWED_WORDS_FILE`
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+const ENTRY_SEPARATOR: char = ':';
+
+pub struct Language {
+    pub code: String,
+    pub answers: Vec<Source>,
+    pub allowed: Vec<Source>,
+    // Words are loaded and compared without their accents
+    pub fold_accents: bool,
+}
+
+// Parses a list of languages separated by commas, each written as `<code>:<answers path>` with
+// an optional list of allowed words (`<code>:<answers path>:<allowed path>`), the answers are
+// the only allowed words otherwise
+pub fn parse_languages(list: &str) -> Result<Vec<Language>, traits::Error> {
+    let mut codes = HashSet::from([DEFAULT_LANGUAGE.to_string()]);
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let parts = entry
+                .splitn(3, ENTRY_SEPARATOR)
+                .map(str::trim)
+                .collect::<Vec<_>>();
+            let (code, answers, allowed) = match parts[..] {
+                [code, answers] => (code, answers, answers),
+                [code, answers, allowed] => (code, answers, allowed),
+                _ => ("", "", ""),
+            };
+            if code.is_empty() || answers.is_empty() || allowed.is_empty() {
+                return Err(traits::Error::InvalidLanguage {
+                    entry: entry.to_string(),
+                });
+            }
+            if !codes.insert(code.to_string()) {
+                return Err(traits::Error::DuplicateLanguage {
+                    code: code.to_string(),
+                });
+            }
+            Ok(Language {
+                code: code.to_string(),
+                answers: vec![Source::File {
+                    path: PathBuf::from(answers),
+                }],
+                allowed: vec![Source::File {
+                    path: PathBuf::from(allowed),
+                }],
+                fold_accents: false,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(sources: &[Source]) -> Vec<String> {
+        sources.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn entries_are_parsed() {
+        let languages =
+            parse_languages(" fr : fr.txt , de:de/answers.txt:de/allowed.txt,,\n").unwrap();
+        assert_eq!(languages.len(), 2);
+        assert_eq!(languages[0].code, "fr");
+        assert_eq!(paths(&languages[0].answers), ["fr.txt"]);
+        // The answers are the only allowed words
+        assert_eq!(paths(&languages[0].allowed), ["fr.txt"]);
+        assert!(!languages[0].fold_accents);
+        assert_eq!(languages[1].code, "de");
+        assert_eq!(paths(&languages[1].answers), ["de/answers.txt"]);
+        assert_eq!(paths(&languages[1].allowed), ["de/allowed.txt"]);
+
+        assert!(parse_languages("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        for entry in [
+            "fr",
+            "fr:",
+            ":fr.txt",
+            "fr::allowed.txt",
+            "fr:answers.txt:",
+            " : ",
+        ] {
+            assert!(
+                matches!(
+                    parse_languages(entry),
+                    Err(traits::Error::InvalidLanguage { entry: e }) if e == entry.trim()
+                ),
+                "{:?}",
+                entry
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_codes_are_rejected() {
+        for (list, code) in [
+            ("fr:a.txt,fr:b.txt", "fr"),
+            ("de:a.txt, fr:a.txt, de:b.txt", "de"),
+            // The default language is configured separately
+            ("en:en.txt", DEFAULT_LANGUAGE),
+        ] {
+            assert!(
+                matches!(
+                    parse_languages(list),
+                    Err(traits::Error::DuplicateLanguage { code: c }) if c == code
+                ),
+                "{:?}",
+                list
+            );
+        }
+    }
+}
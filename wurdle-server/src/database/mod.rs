@@ -1,2 +1,9 @@
+pub mod embedded;
+pub mod file;
 pub mod http;
+pub mod ids;
+pub mod languages;
+pub mod parser;
+pub mod pools;
+pub mod source;
 pub mod traits;
@@ -0,0 +1,142 @@
+use super::traits;
+use crate::game::game::{MAX_WORD_LENGTH, MIN_WORD_LENGTH};
+use crate::game::letters;
+use log::{info, warn};
+use std::collections::HashSet;
+
+// Lines starting with this are ignored
+const COMMENT_PREFIX: char = '#';
+
+#[derive(Default)]
+struct Stats {
+    words: usize,
+    blanks: usize,
+    comments: usize,
+    duplicates: usize,
+    invalid: usize,
+}
+
+fn is_valid_word(word: &str) -> bool {
+    (MIN_WORD_LENGTH as usize..=MAX_WORD_LENGTH as usize).contains(&letters::length(word))
+        && letters::is_word(word)
+}
+
+// One word per line, whatever the line endings, surrounding whitespace, blank lines and comments
+// are ignored and duplicates are skipped
+// Words are lowercased, like the words given by players
+// Every invalid line is reported before failing, lines are numbered from 1
+pub fn parse_words_list<T: FromIterator<String>>(
+    origin: &str,
+    body: &str,
+) -> Result<T, traits::Error> {
+    let mut stats = Stats::default();
+    let mut first_invalid = None;
+    let mut seen = HashSet::new();
+    let mut words = vec![];
+    for (index, line) in body.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            stats.blanks += 1;
+            continue;
+        }
+        if line.starts_with(COMMENT_PREFIX) {
+            stats.comments += 1;
+            continue;
+        }
+        let word = letters::clean(line, false);
+        if !is_valid_word(&word) {
+            warn!(
+                "invalid word {:?} in {} at line {}",
+                word, origin, line_number
+            );
+            stats.invalid += 1;
+            first_invalid.get_or_insert((line_number, word));
+            continue;
+        }
+        if !seen.insert(word.clone()) {
+            warn!(
+                "duplicate word {:?} in {} at line {}",
+                word, origin, line_number
+            );
+            stats.duplicates += 1;
+            continue;
+        }
+        stats.words += 1;
+        words.push(word);
+    }
+
+    info!(
+        "parsed {}: {} words, {} blank lines, {} comments, {} duplicates, {} invalid",
+        origin, stats.words, stats.blanks, stats.comments, stats.duplicates, stats.invalid
+    );
+    if let Some((line, word)) = first_invalid {
+        return Err(traits::Error::InvalidWord {
+            origin: origin.to_string(),
+            line,
+            word,
+            count: stats.invalid,
+        });
+    }
+    Ok(words.into_iter().collect::<T>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(body: &str) -> Result<Vec<String>, traits::Error> {
+        parse_words_list("test", body)
+    }
+
+    #[test]
+    fn line_endings() {
+        for body in [
+            "crane\nsloth\n",
+            "crane\r\nsloth\r\n",
+            "crane\nsloth",
+            "crane\r\nsloth",
+        ] {
+            assert_eq!(parse(body).unwrap(), ["crane", "sloth"], "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn blanks_and_comments_are_ignored() {
+        let body = "# answers\n\ncrane\n   \n\t\n  sloth  \n# end\n\n";
+        assert_eq!(parse(body).unwrap(), ["crane", "sloth"]);
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("# only a comment\n\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn words_are_cleaned() {
+        let body = "Crane\nSTRASSE\nE\u{301}COLE\n";
+        assert_eq!(parse(body).unwrap(), ["crane", "strasse", "école"]);
+    }
+
+    #[test]
+    fn duplicates_are_skipped() {
+        let body = "crane\nsloth\ncrane\nCrane\n sloth\n";
+        assert_eq!(parse(body).unwrap(), ["crane", "sloth"]);
+    }
+
+    #[test]
+    fn invalid_words_are_reported() {
+        let body = "# header\r\ncrane\r\n\r\ncr4ne\r\nabc\r\nsloth\r\nverylongword\r\n";
+        match parse(body) {
+            Err(traits::Error::InvalidWord {
+                origin,
+                line,
+                word,
+                count,
+            }) => {
+                assert_eq!(origin, "test");
+                assert_eq!(line, 4);
+                assert_eq!(word, "cr4ne");
+                assert_eq!(count, 3);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
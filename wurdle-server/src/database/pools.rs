@@ -0,0 +1,140 @@
+use super::http::Fetcher;
+use super::source::{self, Source};
+use super::traits;
+use log::warn;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use ring::digest;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+// Every answer, always available
+pub const FULL_POOL: &str = "full";
+pub const FULL_DESCRIPTION: &str = "Every answer";
+// Used by `restricted: true`, sampled from the answers unless a curated list is given
+pub const RESTRICTED_POOL: &str = "restricted";
+pub const DEFAULT_RESTRICTED_SIZE: usize = 500;
+pub const DEFAULT_RESTRICTED_SEED: &str = "sample me baby";
+
+const ENTRY_SEPARATOR: char = ':';
+
+// A subset of the answers players can choose from
+pub enum Pool {
+    // Random sample of `size` answers of each length
+    Sample {
+        name: String,
+        description: String,
+        size: usize,
+        seed: String,
+    },
+    // Curated list, words which are not answers are ignored
+    Curated {
+        name: String,
+        description: String,
+        source: Source,
+    },
+}
+
+impl Pool {
+    pub fn name(&self) -> &str {
+        match self {
+            Pool::Sample { name, .. } | Pool::Curated { name, .. } => name,
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            Pool::Sample { description, .. } | Pool::Curated { description, .. } => description,
+        }
+    }
+
+    // Pools are built separately for each word length, `answers` are sorted
+    pub async fn select(
+        &self,
+        answers: &[Vec<String>],
+        fetcher: &Fetcher,
+    ) -> Result<Vec<Vec<String>>, traits::Error> {
+        match self {
+            Pool::Sample { size, seed, .. } => {
+                // Lengths are sampled in order so the pools are stable
+                let mut rng = get_rng(seed);
+                Ok(answers
+                    .iter()
+                    .map(|words| {
+                        let mut sample = words.clone();
+                        sample.shuffle(&mut rng);
+                        sample.truncate(*size);
+                        sample.sort();
+                        sample
+                    })
+                    .collect())
+            }
+            Pool::Curated { name, source, .. } => {
+                let curated: HashSet<String> =
+                    source::first_words(std::slice::from_ref(source), fetcher).await?;
+                let selected = answers
+                    .iter()
+                    .map(|words| {
+                        words
+                            .iter()
+                            .filter(|word| curated.contains(*word))
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                let ignored = curated.len() - selected.iter().map(Vec::len).sum::<usize>();
+                if ignored > 0 {
+                    warn!("{} words of pool {} are not answers", ignored, name);
+                }
+                Ok(selected)
+            }
+        }
+    }
+}
+
+// Short seeds are used as is (zero padded), longer ones are hashed to fit
+fn get_rng(seed: &str) -> StdRng {
+    let mut bytes: [u8; 32] = [0; 32];
+    if seed.len() <= bytes.len() {
+        bytes[..seed.len()].copy_from_slice(seed.as_bytes());
+    } else {
+        bytes.copy_from_slice(digest::digest(&digest::SHA256, seed.as_bytes()).as_ref());
+    }
+    StdRng::from_seed(bytes)
+}
+
+// Parses a list of curated pools separated by commas, each written as `<name>:<path>` with an
+// optional description (`<name>:<path>:<description>`)
+pub fn parse_pools(list: &str) -> Result<Vec<Pool>, traits::Error> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let parts = entry
+                .splitn(3, ENTRY_SEPARATOR)
+                .map(str::trim)
+                .collect::<Vec<_>>();
+            let (name, path, description) = match parts[..] {
+                [name, path] => (name, path, None),
+                [name, path, description] => (name, path, Some(description)),
+                _ => ("", "", None),
+            };
+            if name.is_empty() || path.is_empty() {
+                return Err(traits::Error::InvalidPool {
+                    entry: entry.to_string(),
+                });
+            }
+            Ok(Pool::Curated {
+                name: name.to_string(),
+                description: description
+                    .filter(|description| !description.is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("Curated list from {}", path)),
+                source: Source::File {
+                    path: PathBuf::from(path),
+                },
+            })
+        })
+        .collect()
+}
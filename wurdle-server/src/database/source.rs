@@ -0,0 +1,74 @@
+use super::{embedded, file, http, traits};
+use log::{info, warn};
+use std::fmt;
+use std::path::PathBuf;
+
+pub enum Source {
+    Http {
+        url: String,
+    },
+    File {
+        path: PathBuf,
+    },
+    Embedded {
+        name: &'static str,
+        body: &'static str,
+    },
+}
+
+impl Source {
+    pub async fn words<T: FromIterator<String>>(
+        &self,
+        fetcher: &http::Fetcher,
+    ) -> Result<T, traits::Error> {
+        match self {
+            Source::Http { url } => fetcher.get_words_list(url).await,
+            Source::File { path } => file::get_words_list(path).await,
+            Source::Embedded { name, body } => embedded::get_words_list(name, body),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Http { url } => write!(f, "{}", url),
+            Source::File { path } => write!(f, "{}", path.display()),
+            Source::Embedded { name, .. } => write!(f, "embedded:{}", name),
+        }
+    }
+}
+
+// Tries each source in order, returning the words of the first one which loads or why each of
+// them failed
+pub async fn first_words<T: FromIterator<String>>(
+    sources: &[Source],
+    fetcher: &http::Fetcher,
+) -> Result<T, traits::Error> {
+    if sources.is_empty() {
+        return Err(traits::Error::NoSource);
+    }
+    let mut failures = vec![];
+    for source in sources {
+        match source.words::<Vec<String>>(fetcher).await {
+            Ok(words) if words.is_empty() => {
+                warn!("word list from {} is empty", source);
+                failures.push(
+                    traits::Error::EmptyList {
+                        origin: source.to_string(),
+                    }
+                    .to_string(),
+                );
+            }
+            Ok(words) => {
+                info!("loaded {} words from {}", words.len(), source);
+                return Ok(words.into_iter().collect::<T>());
+            }
+            Err(err) => {
+                warn!("could not load word list from {}: {}", source, err);
+                failures.push(format!("{}: {}", source, err));
+            }
+        }
+    }
+    Err(traits::Error::Unavailable { failures })
+}
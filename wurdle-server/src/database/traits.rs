@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,29 +7,86 @@ pub enum Error {
     Http(#[from] reqwest::Error),
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Key(#[from] ring::error::Unspecified),
     #[error("out of bounds: {index} is outside {maximum}")]
     OutOfBounds { index: usize, maximum: usize },
     #[error("missing word: {word}")]
     MissingWord { word: String },
     #[error("invalid id: {id}")]
     InvalidID { id: String },
+    #[error("could not read {path}: {source}")]
+    File {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("{count} invalid words in {origin}, the first one is {word:?} at line {line}")]
+    InvalidWord {
+        origin: String,
+        line: usize,
+        word: String,
+        count: usize,
+    },
+    #[error("empty word list: {origin}")]
+    EmptyList { origin: String },
+    #[error("no word list source configured")]
+    NoSource,
+    #[error("no word list could be loaded ({})", .failures.join("; "))]
+    Unavailable { failures: Vec<String> },
+    #[error("no key configured for word ids")]
+    NoKey,
+    #[error("no words of {length} letters")]
+    UnsupportedLength { length: u8 },
+    #[error("stale word list: version {version:08x} was replaced by {current:08x}")]
+    StaleList { version: u32, current: u32 },
+    #[error("invalid pool {entry:?}, expected `<name>:<path>`")]
+    InvalidPool { entry: String },
+    #[error("no pool {pool} for words of {length} letters")]
+    UnknownPool { pool: String, length: u8 },
+    #[error("invalid language {entry:?}, expected `<code>:<answers path>[:<allowed path>]`")]
+    InvalidLanguage { entry: String },
+    #[error("language {code} is configured more than once")]
+    DuplicateLanguage { code: String },
 }
 
 pub struct Word {
     pub word_id: String,
     pub word: String,
+    // Version of the list the id belongs to
+    pub version: u32,
+}
+
+// A pool of answers and its metadata
+pub struct Pool {
+    pub name: String,
+    pub description: String,
+    // Number of answers for each word length, lengths without answers are omitted
+    pub word_counts: BTreeMap<u8, usize>,
 }
 
 pub trait Database {
     // For specific words
+    // Only accepts the ids given to clients, whatever the length of their word
     fn word_for_id(&self, id: &str) -> Result<Word, Error>;
+    // For the ids stored in sessions, `version` is the version of the list the id was taken
+    // from, unknown for older sessions
+    fn word_for_session(
+        &self,
+        word_length: u8,
+        id: &str,
+        version: Option<u32>,
+    ) -> Result<Word, Error>;
     fn word_exists(&self, word: &str) -> Result<Word, Error>;
     // For random
-    fn word_length(&self, restricted: bool) -> Result<usize, Error>;
-    fn word_for_index(&self, id: usize) -> Result<Word, Error>;
+    // `pool` is the name of a pool of answers, `full` for all of them
+    fn word_count(&self, word_length: u8, pool: &str) -> Result<usize, Error>;
+    fn word_for_index(&self, word_length: u8, pool: &str, index: usize) -> Result<Word, Error>;
     // For guessing
     fn guess_exists(&self, word: &str) -> Result<bool, Error>;
     // For debugging/clients
-    fn allowed_words(&self) -> Result<Vec<String>, Error>;
-    fn answer_words(&self, restricted: bool) -> Result<Vec<String>, Error>;
+    fn allowed_words(&self, word_length: u8) -> Result<Vec<String>, Error>;
+    fn answer_words(&self, word_length: u8, pool: &str) -> Result<Vec<String>, Error>;
+    fn pools(&self) -> Result<Vec<Pool>, Error>;
+    // Words are looked up after `game::letters::clean`, which depends on this
+    fn folds_accents(&self) -> bool;
 }
@@ -0,0 +1,262 @@
+use super::traits;
+use ring::hmac;
+use std::fmt;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+// Time zones range from UTC-12:00 to UTC+14:00
+const MIN_UTC_OFFSET: i32 = -12 * 60;
+const MAX_UTC_OFFSET: i32 = 14 * 60;
+// Puzzle #0
+const FIRST_PUZZLE: (i64, u32, u32) = (2022, 1, 1);
+
+// A calendar day, stored as the number of days since 1970-01-01
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    days: i64,
+}
+
+// See http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+impl Date {
+    // Expects `YYYY-MM-DD`
+    pub fn parse(date: &str) -> Result<Self, traits::Error> {
+        let invalid = || traits::Error::InvalidDate {
+            date: date.to_string(),
+        };
+        if !date.bytes().all(|c| c.is_ascii_digit() || c == b'-') {
+            return Err(invalid());
+        }
+        let parts = date.split('-').collect::<Vec<_>>();
+        let (year, month, day) = match parts[..] {
+            [year, month, day] if year.len() == 4 && month.len() == 2 && day.len() == 2 => (
+                year.parse::<i64>().map_err(|_e| invalid())?,
+                month.parse::<u32>().map_err(|_e| invalid())?,
+                day.parse::<u32>().map_err(|_e| invalid())?,
+            ),
+            _ => return Err(invalid()),
+        };
+        if !(1..=12).contains(&month) {
+            return Err(invalid());
+        }
+        // Out of range days are normalized (e.g. 02-30 becomes 03-02), reject those
+        let days = days_from_civil(year, month, day);
+        if civil_from_days(days) != (year, month, day) {
+            return Err(invalid());
+        }
+        Ok(Self { days })
+    }
+
+    // Current day for a time zone given as an offset from UTC in minutes
+    pub fn today(now: u64, utc_offset: i32) -> Result<Self, traits::Error> {
+        if !(MIN_UTC_OFFSET..=MAX_UTC_OFFSET).contains(&utc_offset) {
+            return Err(traits::Error::InvalidUtcOffset { utc_offset });
+        }
+        let local = now as i64 + utc_offset as i64 * 60;
+        Ok(Self {
+            days: local.div_euclid(SECONDS_PER_DAY),
+        })
+    }
+
+    // Days since the first puzzle, dates before it have no puzzle
+    pub fn puzzle_number(&self) -> Result<u32, traits::Error> {
+        let (year, month, day) = FIRST_PUZZLE;
+        u32::try_from(self.days - days_from_civil(year, month, day)).map_err(|_e| {
+            traits::Error::InvalidDate {
+                date: self.to_string(),
+            }
+        })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.days);
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+// Picks the word of each puzzle, the same seed and word list always give the same words
+#[derive(Clone)]
+pub struct Daily {
+    key: hmac::Key,
+}
+
+impl Daily {
+    pub fn new(seed: &str) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, seed.as_bytes()),
+        }
+    }
+
+    // Index of the word to guess among `word_count` words
+    pub fn index(&self, puzzle_number: u32, word_length: u8, word_count: usize) -> usize {
+        let tag = hmac::sign(
+            &self.key,
+            format!("{}:{}", word_length, puzzle_number).as_bytes(),
+        );
+        let mut value = [0; 8];
+        value.copy_from_slice(&tag.as_ref()[..8]);
+        (u64::from_be_bytes(value) % word_count as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_dates_are_parsed() {
+        let cases = [
+            ("1970-01-01", 0),
+            ("2022-01-01", 18993),
+            ("2022-03-01", 19052),
+            ("2024-02-29", 19782),
+            ("2000-02-29", 11016),
+            ("1969-12-31", -1),
+        ];
+        for (date, days) in cases {
+            let parsed = Date::parse(date).unwrap();
+            assert_eq!(parsed, Date { days }, "{}", date);
+            assert_eq!(parsed.to_string(), date);
+        }
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        for date in [
+            "",
+            "2022-1-01",
+            "22-01-01",
+            "2022/01/01",
+            "2022-01-01T00:00",
+            " 2022-01-01",
+            "2022-01",
+            "2022-00-10",
+            "2022-13-01",
+            "2022-01-00",
+            "2022-01-32",
+            "2022-04-31",
+            // Not leap years
+            "2022-02-29",
+            "2100-02-29",
+            "+022-01-01",
+        ] {
+            assert!(
+                matches!(Date::parse(date), Err(traits::Error::InvalidDate { date: d }) if d == date),
+                "{:?}",
+                date
+            );
+        }
+    }
+
+    #[test]
+    fn today_depends_on_the_time_zone() {
+        // 2022-03-01T23:30:00Z
+        let now = 1646177400;
+        let cases = [
+            (0, "2022-03-01"),
+            (29, "2022-03-01"),
+            (30, "2022-03-02"),
+            (MAX_UTC_OFFSET, "2022-03-02"),
+            (MIN_UTC_OFFSET, "2022-03-01"),
+        ];
+        for (utc_offset, expected) in cases {
+            assert_eq!(
+                Date::today(now, utc_offset).unwrap().to_string(),
+                expected,
+                "{}",
+                utc_offset
+            );
+        }
+        // 2022-03-01T00:00:00Z
+        assert_eq!(
+            Date::today(1646092800, 0).unwrap().to_string(),
+            "2022-03-01"
+        );
+        assert_eq!(
+            Date::today(1646092800, -1).unwrap().to_string(),
+            "2022-02-28"
+        );
+
+        for utc_offset in [MIN_UTC_OFFSET - 1, MAX_UTC_OFFSET + 1, i32::MAX] {
+            assert!(matches!(
+                Date::today(now, utc_offset),
+                Err(traits::Error::InvalidUtcOffset { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn puzzles_are_numbered_from_the_first_one() {
+        let cases = [
+            ("2022-01-01", 0),
+            ("2022-01-02", 1),
+            ("2022-12-31", 364),
+            ("2024-03-01", 790),
+        ];
+        for (date, puzzle_number) in cases {
+            assert_eq!(
+                Date::parse(date).unwrap().puzzle_number().unwrap(),
+                puzzle_number,
+                "{}",
+                date
+            );
+        }
+        assert!(matches!(
+            Date::parse("2021-12-31").unwrap().puzzle_number(),
+            Err(traits::Error::InvalidDate { date }) if date == "2021-12-31"
+        ));
+    }
+
+    #[test]
+    fn words_depend_on_the_seed() {
+        let daily = Daily::new("seed");
+        let indexes = (0..50)
+            .map(|puzzle_number| daily.index(puzzle_number, 5, 2315))
+            .collect::<Vec<_>>();
+        assert!(indexes.iter().all(|index| *index < 2315));
+        assert_eq!(
+            indexes,
+            (0..50)
+                .map(|puzzle_number| Daily::new("seed").index(puzzle_number, 5, 2315))
+                .collect::<Vec<_>>()
+        );
+        assert_ne!(
+            indexes,
+            (0..50)
+                .map(|puzzle_number| Daily::new("other").index(puzzle_number, 5, 2315))
+                .collect::<Vec<_>>()
+        );
+        // Each length has its own sequence of words
+        assert_ne!(
+            indexes,
+            (0..50)
+                .map(|puzzle_number| daily.index(puzzle_number, 6, 2315))
+                .collect::<Vec<_>>()
+        );
+    }
+}
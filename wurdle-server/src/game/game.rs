@@ -0,0 +1,177 @@
+use super::hard_mode::Constraints;
+use super::letters;
+use super::scoring::{self, LetterResult};
+use super::traits;
+use crate::session::session::{Guess, Session, Status};
+
+pub const DEFAULT_MAX_GUESSES: u8 = 6;
+pub const MIN_MAX_GUESSES: u8 = 1;
+pub const MAX_MAX_GUESSES: u8 = 20;
+pub const DEFAULT_WORD_LENGTH: u8 = 5;
+pub const MIN_WORD_LENGTH: u8 = 4;
+pub const MAX_WORD_LENGTH: u8 = 8;
+
+pub struct Turn {
+    pub session: Session,
+    pub result: Vec<LetterResult>,
+}
+
+pub struct Game<'a> {
+    answer: &'a str,
+}
+
+impl<'a> Game<'a> {
+    pub fn new(answer: &'a str) -> Self {
+        Self { answer }
+    }
+
+    // Also exposed so the length can be checked before looking the guess up
+    pub fn check_length(&self, guess: &str) -> Result<(), traits::Error> {
+        let expected = letters::length(self.answer);
+        let actual = letters::length(guess);
+        if actual != expected {
+            return Err(traits::Error::WrongLength { expected, actual });
+        }
+        Ok(())
+    }
+
+    // Also exposed so finished sessions are rejected before the guess is checked in any way,
+    // gives the number of guesses used so far
+    pub fn check_in_progress(session: &Session) -> Result<u8, traits::Error> {
+        match session.status {
+            Status::InProgress { used_guesses } => Ok(used_guesses),
+            Status::Failed | Status::Won { .. } => Err(traits::Error::SessionFinished),
+        }
+    }
+
+    pub fn guess(&self, mut session: Session, guess: &str) -> Result<Turn, traits::Error> {
+        let used_guesses = Self::check_in_progress(&session)? + 1;
+
+        self.check_length(guess)?;
+        let result = scoring::evaluate(&letters::letters(self.answer), &letters::letters(guess));
+
+        if session.hard_mode {
+            Constraints::from_history(&session.history).check(guess)?;
+        }
+        session.history.push(Guess {
+            word: guess.to_string(),
+            result: result.clone(),
+        });
+
+        let status = if self.answer == guess {
+            Status::Won { used_guesses }
+        } else if used_guesses >= session.max_guesses {
+            Status::Failed
+        } else {
+            Status::InProgress { used_guesses }
+        };
+
+        Ok(Turn {
+            session: Session { status, ..session },
+            result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(max_guesses: u8) -> Session {
+        Session::new("id", 5, false, max_guesses, None)
+    }
+
+    // Plays every guess, stopping at the first error
+    fn play(
+        answer: &str,
+        mut session: Session,
+        guesses: &[&str],
+    ) -> Result<Session, traits::Error> {
+        let game = Game::new(answer);
+        for guess in guesses {
+            session = game.guess(session, guess)?.session;
+        }
+        Ok(session)
+    }
+
+    #[test]
+    fn guessing_the_answer_wins() {
+        let session = play("crane", session(6), &["sloth", "crane"]).unwrap();
+        assert!(matches!(session.status, Status::Won { used_guesses: 2 }));
+        assert_eq!(session.used_guesses(), 2);
+        assert_eq!(session.history.len(), 2);
+        assert_eq!(session.history[1].word, "crane");
+        assert!(session.history[1]
+            .result
+            .iter()
+            .all(|result| *result == LetterResult::Valid));
+    }
+
+    #[test]
+    fn other_guesses_keep_the_game_going() {
+        let turn = Game::new("crane").guess(session(6), "nacre").unwrap();
+        assert!(matches!(
+            turn.session.status,
+            Status::InProgress { used_guesses: 1 }
+        ));
+        assert_eq!(turn.result, turn.session.history[0].result);
+    }
+
+    #[test]
+    fn running_out_of_guesses_fails() {
+        let failed = play("crane", session(6), &["sloth"; 6]).unwrap();
+        assert!(matches!(failed.status, Status::Failed));
+        assert_eq!(failed.used_guesses(), 6);
+
+        // Guessing the answer with the last guess still wins
+        let guesses = ["sloth", "sloth", "sloth", "sloth", "sloth", "crane"];
+        let won = play("crane", session(6), &guesses).unwrap();
+        assert!(matches!(won.status, Status::Won { used_guesses: 6 }));
+    }
+
+    #[test]
+    fn finished_sessions_reject_guesses() {
+        for guesses in [&["crane"][..], &["sloth"; 6][..]] {
+            let session = play("crane", session(6), guesses).unwrap();
+            assert!(matches!(
+                play("crane", session, &["crane"]),
+                Err(traits::Error::SessionFinished)
+            ));
+        }
+    }
+
+    #[test]
+    fn guesses_must_have_the_length_of_the_answer() {
+        for (guess, actual) in [("cran", 4), ("cranes", 6), ("", 0)] {
+            assert!(matches!(
+                Game::new("crane").guess(session(6), guess),
+                Err(traits::Error::WrongLength { expected: 5, actual: a }) if a == actual
+            ));
+        }
+        // Letters are counted, not bytes
+        assert!(Game::new("élan").check_length("lané").is_ok());
+    }
+
+    #[test]
+    fn max_guesses_ends_the_game() {
+        for max_guesses in [MIN_MAX_GUESSES, 4, DEFAULT_MAX_GUESSES, 10, MAX_MAX_GUESSES] {
+            let mut session = session(max_guesses);
+            for used_guesses in 1..max_guesses {
+                session = Game::new("crane").guess(session, "sloth").unwrap().session;
+                assert!(
+                    matches!(session.status, Status::InProgress { used_guesses: u } if u == used_guesses),
+                    "{} of {}",
+                    used_guesses,
+                    max_guesses
+                );
+            }
+            let session = Game::new("crane").guess(session, "sloth").unwrap().session;
+            assert!(matches!(session.status, Status::Failed), "{}", max_guesses);
+            assert_eq!(session.used_guesses(), max_guesses);
+            assert!(matches!(
+                Game::new("crane").guess(session, "crane"),
+                Err(traits::Error::SessionFinished)
+            ));
+        }
+    }
+}
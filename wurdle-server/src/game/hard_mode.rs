@@ -0,0 +1,208 @@
+use super::letters;
+use super::scoring::LetterResult;
+use super::traits;
+use crate::session::session::Guess;
+use std::collections::BTreeMap;
+
+// What previous guesses revealed: letters known to be at a given position and the minimum
+// number of times each revealed letter appears in the answer
+#[derive(Debug, Default)]
+pub struct Constraints {
+    placed: Vec<Option<String>>,
+    required: BTreeMap<String, usize>,
+}
+
+impl Constraints {
+    pub fn from_history(history: &[Guess]) -> Self {
+        let mut constraints = Self::default();
+        for guess in history {
+            constraints.update(&guess.word, &guess.result);
+        }
+        constraints
+    }
+
+    pub fn check(&self, guess: &str) -> Result<(), traits::Error> {
+        let letters = letters::letters(guess);
+        for (position, expected) in self.placed.iter().enumerate() {
+            if let Some(letter) = expected {
+                if letters.get(position) != Some(&letter.as_str()) {
+                    return Err(traits::Error::HardModePlacement {
+                        letter: letter.clone(),
+                        position: position + 1,
+                    });
+                }
+            }
+        }
+        for (letter, count) in self.required.iter() {
+            if letters
+                .iter()
+                .filter(|received| **received == letter)
+                .count()
+                < *count
+            {
+                return Err(traits::Error::HardModeMissing {
+                    letter: letter.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, guess: &str, result: &[LetterResult]) {
+        if self.placed.len() < result.len() {
+            self.placed.resize(result.len(), None);
+        }
+
+        let mut revealed: BTreeMap<&str, usize> = BTreeMap::new();
+        for (position, (letter, indicator)) in letters::letters(guess)
+            .into_iter()
+            .zip(result.iter())
+            .enumerate()
+        {
+            match indicator {
+                LetterResult::Valid => {
+                    self.placed[position] = Some(letter.to_string());
+                    *revealed.entry(letter).or_insert(0) += 1;
+                }
+                LetterResult::WrongPlace => *revealed.entry(letter).or_insert(0) += 1,
+                LetterResult::Wrong => (),
+            }
+        }
+
+        for (letter, count) in revealed {
+            let required = self.required.entry(letter.to_string()).or_insert(0);
+            *required = (*required).max(count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::scoring;
+
+    // Constraints revealed by guessing each word against `answer`
+    fn constraints(answer: &str, guesses: &[&str]) -> Constraints {
+        let mut constraints = Constraints::default();
+        for guess in guesses {
+            let result = scoring::evaluate(&letters::letters(answer), &letters::letters(guess));
+            constraints.update(guess, &result);
+        }
+        constraints
+    }
+
+    #[test]
+    fn update_table() {
+        // Placed letters by position, then the minimum count of each revealed letter
+        let cases = [
+            ("crane", &["sloth"][..], "_____", &[][..]),
+            (
+                "crane",
+                &["nacre"],
+                "____e",
+                &[("a", 1), ("c", 1), ("e", 1), ("n", 1), ("r", 1)],
+            ),
+            (
+                "crane",
+                &["track", "crepe"],
+                "cra_e",
+                &[("a", 1), ("c", 1), ("e", 1), ("r", 1)],
+            ),
+            (
+                "abbey",
+                &["babes"],
+                "__be_",
+                &[("a", 1), ("b", 2), ("e", 1)],
+            ),
+            // A single revealed copy doesn't lower what an earlier guess required
+            (
+                "abbey",
+                &["babes", "bloke"],
+                "__be_",
+                &[("a", 1), ("b", 2), ("e", 1)],
+            ),
+            (
+                "abbey",
+                &["kebab"],
+                "__b__",
+                &[("a", 1), ("b", 2), ("e", 1)],
+            ),
+            // Extra copies which are marked wrong are not required
+            ("those", &["geese"], "___se", &[("e", 1), ("s", 1)]),
+            (
+                "élan",
+                &["lané"],
+                "____",
+                &[("a", 1), ("l", 1), ("n", 1), ("é", 1)],
+            ),
+        ];
+        for (answer, guesses, placed, required) in cases {
+            let constraints = constraints(answer, guesses);
+            let actual = constraints
+                .placed
+                .iter()
+                .map(|letter| letter.as_deref().unwrap_or("_"))
+                .collect::<String>();
+            assert_eq!(actual, placed, "{} / {:?}", answer, guesses);
+            let required = required
+                .iter()
+                .map(|(letter, count)| (letter.to_string(), *count))
+                .collect::<BTreeMap<_, _>>();
+            assert_eq!(constraints.required, required, "{} / {:?}", answer, guesses);
+        }
+    }
+
+    #[test]
+    fn check_table() {
+        // `None` when the guess is allowed, the letter and position (0 if it is missing)
+        // otherwise
+        let cases = [
+            ("crane", &[][..], "zzzzz", None),
+            ("crane", &["sloth"], "sloth", None),
+            ("crane", &["nacre"], "caner", Some(("e", 5))),
+            ("crane", &["nacre"], "acne", Some(("e", 5))),
+            ("crane", &["nacre"], "crane", None),
+            ("crane", &["nacre"], "rance", None),
+            ("crane", &["nacre"], "dance", Some(("r", 0))),
+            ("abbey", &["babes"], "kebab", Some(("e", 4))),
+            ("abbey", &["babes"], "tabes", Some(("b", 0))),
+            ("abbey", &["babes"], "ebbed", Some(("a", 0))),
+            ("abbey", &["babes"], "abbey", None),
+            ("abbey", &["babes"], "babel", None),
+        ];
+        for (answer, guesses, guess, expected) in cases {
+            let actual = match constraints(answer, guesses).check(guess) {
+                Ok(()) => None,
+                Err(traits::Error::HardModePlacement { letter, position }) => {
+                    Some((letter, position))
+                }
+                Err(traits::Error::HardModeMissing { letter }) => Some((letter, 0)),
+                Err(err) => panic!("unexpected error: {}", err),
+            };
+            assert_eq!(
+                actual,
+                expected.map(|(letter, position)| (letter.to_string(), position)),
+                "{} / {:?} / {}",
+                answer,
+                guesses,
+                guess
+            );
+        }
+    }
+
+    #[test]
+    fn history_gives_the_same_constraints() {
+        let answer = letters::letters("abbey");
+        let history = ["babes", "kebab"]
+            .iter()
+            .map(|word| Guess {
+                word: word.to_string(),
+                result: scoring::evaluate(&answer, &letters::letters(word)),
+            })
+            .collect::<Vec<_>>();
+        let expected = constraints("abbey", &["babes", "kebab"]);
+        let actual = Constraints::from_history(&history);
+        assert_eq!(actual.placed, expected.placed);
+        assert_eq!(actual.required, expected.required);
+    }
+}
@@ -0,0 +1,115 @@
+use super::traits;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+// A letter is a grapheme, so that accented and non-Latin letters count as a single letter
+// whether they are written precomposed or not
+pub fn letters(word: &str) -> Vec<&str> {
+    word.graphemes(true).collect()
+}
+
+pub fn length(word: &str) -> usize {
+    word.graphemes(true).count()
+}
+
+// Words are compared in NFC, optionally without their accents (e.g. `é` becomes `e`)
+pub fn normalize(word: &str, fold_accents: bool) -> String {
+    if fold_accents {
+        word.nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .nfc()
+            .collect()
+    } else {
+        word.nfc().collect()
+    }
+}
+
+// Lowercase letters of any script, accents included
+pub fn is_word(word: &str) -> bool {
+    word.chars()
+        .all(|c| (c.is_alphabetic() && !c.is_uppercase()) || is_combining_mark(c))
+}
+
+// Words given by players, surrounding whitespace and case are ignored
+pub fn clean(word: &str, fold_accents: bool) -> String {
+    normalize(&word.trim().to_lowercase(), fold_accents)
+}
+
+// Same as `clean`, anything which isn't made of letters is rejected
+pub fn parse(word: &str, fold_accents: bool) -> Result<String, traits::Error> {
+    let cleaned = clean(word, fold_accents);
+    if cleaned.is_empty() || !is_word(&cleaned) {
+        return Err(traits::Error::InvalidLetters {
+            word: word.to_string(),
+        });
+    }
+    Ok(cleaned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_are_graphemes() {
+        assert_eq!(letters("crane"), ["c", "r", "a", "n", "e"]);
+        assert_eq!(letters("été"), ["é", "t", "é"]);
+        // Decomposed: `e` followed by a combining acute accent
+        assert_eq!(letters("e\u{301}te\u{301}"), ["e\u{301}", "t", "e\u{301}"]);
+        assert_eq!(length("straße"), 6);
+        assert_eq!(length("e\u{301}te\u{301}"), 3);
+    }
+
+    #[test]
+    fn normalize_words() {
+        assert_eq!(normalize("e\u{301}te\u{301}", false), "été");
+        assert_eq!(normalize("été", false), "été");
+        assert_eq!(normalize("e\u{301}te\u{301}", true), "ete");
+        assert_eq!(normalize("Ça", true), "Ca");
+    }
+
+    #[test]
+    fn clean_words() {
+        let cases = [
+            ("crane", "crane"),
+            ("CRANE", "crane"),
+            ("  Crane\n", "crane"),
+            ("\tÉCOLE ", "école"),
+            ("E\u{301}COLE", "école"),
+            ("", ""),
+            ("cr4ne", "cr4ne"),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(clean(word, false), expected, "{:?}", word);
+        }
+        assert_eq!(clean(" École ", true), "ecole");
+    }
+
+    #[test]
+    fn parse_words() {
+        assert_eq!(parse(" CRANE ", false).unwrap(), "crane");
+        assert_eq!(parse("Über", false).unwrap(), "über");
+        assert_eq!(parse("Über", true).unwrap(), "uber");
+        assert_eq!(parse("пицца", false).unwrap(), "пицца");
+        for word in [
+            "",
+            "   ",
+            "cr4ne",
+            "cr ne",
+            "cr-ne",
+            "crane!",
+            "l'eau",
+            "🙂🙂🙂🙂🙂",
+        ] {
+            assert!(
+                matches!(
+                    parse(word, false),
+                    Err(traits::Error::InvalidLetters { word: invalid }) if invalid == word
+                ),
+                "{:?}",
+                word
+            );
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod daily;
+pub mod game;
+pub mod hard_mode;
+pub mod letters;
+pub mod scoring;
+pub mod traits;
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LetterResult {
+    Valid,
+    WrongPlace,
+    Wrong,
+}
+
+impl LetterResult {
+    // 2-bit representation, used to pack results in binary sessions
+    pub fn to_bits(self) -> u8 {
+        match self {
+            LetterResult::Wrong => 0,
+            LetterResult::WrongPlace => 1,
+            LetterResult::Valid => 2,
+        }
+    }
+
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0 => Some(LetterResult::Wrong),
+            1 => Some(LetterResult::WrongPlace),
+            2 => Some(LetterResult::Valid),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LetterResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            LetterResult::Valid => "valid",
+            LetterResult::WrongPlace => "wrong_place",
+            LetterResult::Wrong => "wrong",
+        })
+    }
+}
+
+// Canonical two-pass scoring: exact matches consume their letter first, so that a repeated
+// letter is only marked as misplaced when the answer still has an unmatched copy of it
+// Both words must have the same number of letters
+pub fn evaluate<T: Eq + Hash>(answer: &[T], guess: &[T]) -> Vec<LetterResult> {
+    let mut result = vec![LetterResult::Wrong; guess.len()];
+
+    let mut letters_lookup: HashMap<&T, u32> = HashMap::new();
+    for (position, (expected, received)) in answer.iter().zip(guess.iter()).enumerate() {
+        if expected == received {
+            result[position] = LetterResult::Valid;
+        } else {
+            let amount = letters_lookup.entry(expected).or_insert(0);
+            *amount += 1;
+        }
+    }
+
+    for (position, received) in guess.iter().enumerate() {
+        if result[position] == LetterResult::Valid {
+            continue;
+        }
+        if let Some(amount) = letters_lookup.get_mut(received) {
+            if *amount > 0 {
+                *amount -= 1;
+                result[position] = LetterResult::WrongPlace;
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::letters;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn score(answer: &str, guess: &str) -> String {
+        let result = evaluate(&letters::letters(answer), &letters::letters(guess));
+        result
+            .into_iter()
+            .map(|letter| match letter {
+                LetterResult::Valid => 'v',
+                LetterResult::WrongPlace => 'p',
+                LetterResult::Wrong => 'w',
+            })
+            .collect()
+    }
+
+    // Greens first, then each remaining guessed letter takes the first unused copy in the answer
+    fn reference(answer: &[char], guess: &[char]) -> Vec<LetterResult> {
+        let mut used = vec![false; answer.len()];
+        let mut result = vec![LetterResult::Wrong; guess.len()];
+        for position in 0..guess.len() {
+            if answer[position] == guess[position] {
+                used[position] = true;
+                result[position] = LetterResult::Valid;
+            }
+        }
+        for position in 0..guess.len() {
+            if result[position] == LetterResult::Valid {
+                continue;
+            }
+            if let Some(other) =
+                (0..answer.len()).find(|&i| !used[i] && answer[i] == guess[position])
+            {
+                used[other] = true;
+                result[position] = LetterResult::WrongPlace;
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn evaluate_table() {
+        let cases = [
+            ("crane", "crane", "vvvvv"),
+            ("crane", "moist", "wwwww"),
+            ("crane", "nacre", "ppppv"),
+            // Exact matches consume their letter before misplaced ones
+            ("abbey", "bbbxx", "wvvww"),
+            ("abbey", "kebab", "wpvpp"),
+            ("abbey", "babes", "ppvvw"),
+            ("those", "geese", "wwwvv"),
+            ("lever", "eerie", "pvpww"),
+            ("speed", "abide", "wwwpp"),
+            ("speed", "eeeee", "wwvvw"),
+            ("aaabb", "bbaaa", "ppvpp"),
+            ("allee", "eella", "ppvpp"),
+            ("élan", "lané", "pppp"),
+            ("über", "ubér", "wvwv"),
+        ];
+        for (answer, guess, expected) in cases {
+            assert_eq!(score(answer, guess), expected, "{} / {}", answer, guess);
+        }
+    }
+
+    #[test]
+    fn evaluate_matches_reference() {
+        // A small alphabet to get many repeated letters
+        let alphabet = ['a', 'b', 'c'];
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..10_000 {
+            let length = rng.gen_range(1..=8);
+            let mut word = || {
+                (0..length)
+                    .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                    .collect::<Vec<_>>()
+            };
+            let answer = word();
+            let guess = word();
+            assert_eq!(
+                evaluate(&answer, &guess),
+                reference(&answer, &guess),
+                "{:?} / {:?}",
+                answer,
+                guess
+            );
+        }
+    }
+}
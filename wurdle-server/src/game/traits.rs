@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("session is already finished")]
+    SessionFinished,
+    #[error("wrong length: expected {expected} letters, got {actual}")]
+    WrongLength { expected: usize, actual: usize },
+    #[error("hard mode: letter {letter:?} must be in position {position}")]
+    HardModePlacement { letter: String, position: usize },
+    #[error("hard mode: guess must contain {letter:?}")]
+    HardModeMissing { letter: String },
+    #[error("only letters are allowed: {word:?}")]
+    InvalidLetters { word: String },
+    #[error("invalid date: {date}")]
+    InvalidDate { date: String },
+    #[error("no puzzle yet for {date}")]
+    FutureDate { date: String },
+    #[error("invalid UTC offset: {utc_offset} minutes")]
+    InvalidUtcOffset { utc_offset: i32 },
+}
@@ -0,0 +1,461 @@
+use crate::database;
+use crate::game;
+use crate::session;
+use serde_json::json;
+use std::fmt::Display;
+use thiserror::Error;
+use wurdle_openapi::models;
+
+// Stable error IDs, keep in sync with the catalogue in `api/openapi.yaml`
+pub const INTERNAL_ERROR: &str = "abe15c99-eaa4-4fb0-a657-b88430fb8910";
+pub const INVALID_ID_ERROR: &str = "6756064a-ac2f-4608-80cb-b6ce7a85c0c5";
+pub const MISSING_WORD_ERROR: &str = "5b91e445-8ac0-4f94-9105-23495ac00c0d";
+pub const NOT_IN_GUESS_LIST_ERROR: &str = "012e6e93-8df1-4b7a-b9cc-04b76ff6e0ff";
+pub const WRONG_LENGTH_ERROR: &str = "03ef08b5-bae9-4bdc-bd21-b15d5887a93e";
+pub const HARD_MODE_ERROR: &str = "caf90009-4fa8-4438-9a38-86cf17359b95";
+pub const SESSION_FINISHED_ERROR: &str = "3ab47b9e-896c-4acc-90d7-92ca1d54a12b";
+pub const BAD_SIGNATURE_ERROR: &str = "198021b2-1059-418c-89b3-214c2d162ede";
+pub const MALFORMED_SESSION_ERROR: &str = "cea509b1-da8f-4434-a687-46e645ab7823";
+pub const SESSION_EXPIRED_ERROR: &str = "6950cda6-0ad1-43e3-bb98-2463c48aad71";
+pub const UNSUPPORTED_WORD_LENGTH_ERROR: &str = "712a25aa-6f3a-4c91-8e69-18a276dc3c1b";
+pub const INVALID_MAX_GUESSES_ERROR: &str = "91cc7ee5-072d-464b-acc4-af7bc0e0c88c";
+pub const INVALID_DATE_ERROR: &str = "f6ddf224-ef13-4f90-854a-de1410795274";
+pub const FUTURE_DATE_ERROR: &str = "4dc094b3-79df-4390-be1b-2e26552bcc7e";
+pub const INVALID_UTC_OFFSET_ERROR: &str = "cdd68e68-e1d0-42dc-9d97-bd5c0cc7206b";
+pub const STALE_WORD_LIST_ERROR: &str = "cb0110d9-ec55-4794-a925-b29e8f09cfff";
+pub const UNKNOWN_POOL_ERROR: &str = "f7884cea-a5e7-4f4a-b34d-adc6e72cb467";
+pub const UNKNOWN_LANGUAGE_ERROR: &str = "f49650ff-83d6-43dd-bc7e-4f9790251701";
+pub const INVALID_LETTERS_ERROR: &str = "4bb1a1e0-f45b-40a1-9548-4ae426a0e327";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid id: {id}")]
+    InvalidID { id: String },
+    #[error("missing word: {word}")]
+    MissingWord { word: String },
+    #[error("word not on the list: {guess}")]
+    NotInGuessList { guess: String },
+    #[error("wrong length: expected {expected} letters, got {actual}")]
+    WrongLength { expected: usize, actual: usize },
+    #[error("hard mode: letter {letter:?} must be in position {position}")]
+    HardModePlacement { letter: String, position: usize },
+    #[error("hard mode: guess must contain {letter:?}")]
+    HardModeMissing { letter: String },
+    #[error("only letters are allowed: {word:?}")]
+    InvalidLetters { word: String },
+    #[error("session is already finished")]
+    SessionFinished,
+    #[error("invalid session signature")]
+    BadSignature,
+    #[error("malformed session: {reason}")]
+    MalformedSession { reason: String },
+    #[error("session expired at {expires_at}")]
+    SessionExpired { expires_at: u64 },
+    #[error("unsupported word length: {length}")]
+    UnsupportedWordLength { length: i32 },
+    #[error("invalid maximum number of guesses: {max_guesses}")]
+    InvalidMaxGuesses { max_guesses: i32 },
+    #[error("invalid date: {date}")]
+    InvalidDate { date: String },
+    #[error("no puzzle yet for {date}")]
+    FutureDate { date: String },
+    #[error("invalid UTC offset: {utc_offset} minutes")]
+    InvalidUtcOffset { utc_offset: i32 },
+    #[error("the word list changed since this session started")]
+    StaleWordList { version: u32, current: u32 },
+    #[error("no pool {pool} for words of {length} letters")]
+    UnknownPool { pool: String, length: u8 },
+    #[error("unknown language: {language}")]
+    UnknownLanguage { language: String },
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+impl Error {
+    pub fn internal<E: Display>(err: E) -> Self {
+        Error::Internal {
+            message: err.to_string(),
+        }
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Error::InvalidID { .. } => INVALID_ID_ERROR,
+            Error::MissingWord { .. } => MISSING_WORD_ERROR,
+            Error::NotInGuessList { .. } => NOT_IN_GUESS_LIST_ERROR,
+            Error::WrongLength { .. } => WRONG_LENGTH_ERROR,
+            Error::HardModePlacement { .. } | Error::HardModeMissing { .. } => HARD_MODE_ERROR,
+            Error::InvalidLetters { .. } => INVALID_LETTERS_ERROR,
+            Error::SessionFinished => SESSION_FINISHED_ERROR,
+            Error::BadSignature => BAD_SIGNATURE_ERROR,
+            Error::MalformedSession { .. } => MALFORMED_SESSION_ERROR,
+            Error::SessionExpired { .. } => SESSION_EXPIRED_ERROR,
+            Error::UnsupportedWordLength { .. } => UNSUPPORTED_WORD_LENGTH_ERROR,
+            Error::InvalidMaxGuesses { .. } => INVALID_MAX_GUESSES_ERROR,
+            Error::InvalidDate { .. } => INVALID_DATE_ERROR,
+            Error::FutureDate { .. } => FUTURE_DATE_ERROR,
+            Error::InvalidUtcOffset { .. } => INVALID_UTC_OFFSET_ERROR,
+            Error::StaleWordList { .. } => STALE_WORD_LIST_ERROR,
+            Error::UnknownPool { .. } => UNKNOWN_POOL_ERROR,
+            Error::UnknownLanguage { .. } => UNKNOWN_LANGUAGE_ERROR,
+            Error::Internal { .. } => INTERNAL_ERROR,
+        }
+    }
+
+    // Client errors are reported with a 400, everything else is a 500
+    pub fn is_client_error(&self) -> bool {
+        !matches!(self, Error::Internal { .. })
+    }
+
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Error::InvalidID { id } => Some(json!({ "id": id })),
+            Error::MissingWord { word } => Some(json!({ "word": word })),
+            Error::NotInGuessList { guess } => Some(json!({ "guess": guess })),
+            Error::WrongLength { expected, actual } => {
+                Some(json!({ "expected": expected, "actual": actual }))
+            }
+            Error::HardModePlacement { letter, position } => Some(json!({
+                "rule": "placement",
+                "letter": letter,
+                "position": position,
+            })),
+            Error::HardModeMissing { letter } => Some(json!({
+                "rule": "missing",
+                "letter": letter,
+            })),
+            Error::InvalidLetters { word } => Some(json!({ "word": word })),
+            Error::SessionExpired { expires_at } => Some(json!({ "expires_at": expires_at })),
+            Error::UnsupportedWordLength { length } => Some(json!({ "length": length })),
+            Error::InvalidMaxGuesses { max_guesses } => Some(json!({ "max_guesses": max_guesses })),
+            Error::InvalidDate { date } | Error::FutureDate { date } => {
+                Some(json!({ "date": date }))
+            }
+            Error::InvalidUtcOffset { utc_offset } => Some(json!({ "utc_offset": utc_offset })),
+            Error::StaleWordList { version, current } => Some(json!({
+                "version": format!("{:08x}", version),
+                "current": format!("{:08x}", current),
+            })),
+            Error::UnknownPool { pool, length } => Some(json!({ "pool": pool, "length": length })),
+            Error::UnknownLanguage { language } => Some(json!({ "language": language })),
+            Error::SessionFinished
+            | Error::BadSignature
+            | Error::MalformedSession { .. }
+            | Error::Internal { .. } => None,
+        }
+    }
+}
+
+impl From<database::traits::Error> for Error {
+    fn from(err: database::traits::Error) -> Self {
+        match err {
+            database::traits::Error::InvalidID { id } => Error::InvalidID { id },
+            database::traits::Error::OutOfBounds { index, .. } => Error::InvalidID {
+                id: index.to_string(),
+            },
+            database::traits::Error::MissingWord { word } => Error::MissingWord { word },
+            database::traits::Error::StaleList { version, current } => {
+                Error::StaleWordList { version, current }
+            }
+            database::traits::Error::UnsupportedLength { length } => Error::UnsupportedWordLength {
+                length: length.into(),
+            },
+            database::traits::Error::UnknownPool { pool, length } => {
+                Error::UnknownPool { pool, length }
+            }
+            err => Error::internal(err),
+        }
+    }
+}
+
+impl From<game::traits::Error> for Error {
+    fn from(err: game::traits::Error) -> Self {
+        match err {
+            game::traits::Error::SessionFinished => Error::SessionFinished,
+            game::traits::Error::WrongLength { expected, actual } => {
+                Error::WrongLength { expected, actual }
+            }
+            game::traits::Error::HardModePlacement { letter, position } => {
+                Error::HardModePlacement { letter, position }
+            }
+            game::traits::Error::HardModeMissing { letter } => Error::HardModeMissing { letter },
+            game::traits::Error::InvalidLetters { word } => Error::InvalidLetters { word },
+            game::traits::Error::InvalidDate { date } => Error::InvalidDate { date },
+            game::traits::Error::FutureDate { date } => Error::FutureDate { date },
+            game::traits::Error::InvalidUtcOffset { utc_offset } => {
+                Error::InvalidUtcOffset { utc_offset }
+            }
+        }
+    }
+}
+
+// Only meant for errors coming from decoding a session given by a client, errors when encoding
+// a session should be reported using `Error::internal`
+impl From<session::traits::Error> for Error {
+    fn from(err: session::traits::Error) -> Self {
+        match err {
+            session::traits::Error::Signing(_) | session::traits::Error::UnknownKey { .. } => {
+                Error::BadSignature
+            }
+            session::traits::Error::Expired { expires_at } => Error::SessionExpired { expires_at },
+            session::traits::Error::Compression(_) => Error::internal(err),
+            err => Error::MalformedSession {
+                reason: err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<Error> for models::Error {
+    fn from(err: Error) -> Self {
+        models::Error {
+            id: err.id().to_string(),
+            message: err.to_string(),
+            details: err
+                .details()
+                .and_then(|details| serde_json::from_value(details).ok()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    static CATALOGUE: &str = include_str!("../../api/openapi.yaml");
+
+    // Status and details column of each row of the catalogue, by id
+    fn catalogue() -> HashMap<String, (u16, String)> {
+        CATALOGUE
+            .lines()
+            .filter_map(|line| {
+                let columns = line.trim().split(" | ").collect::<Vec<_>>();
+                match columns[..] {
+                    [id, status, _description, details] => Some((
+                        id.trim_start_matches("| ").trim_matches('`').to_string(),
+                        (
+                            status.parse().ok()?,
+                            details.trim_end_matches(" |").to_string(),
+                        ),
+                    )),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn errors_match_the_catalogue() {
+        let cases: Vec<(Error, &str, Option<serde_json::Value>)> = vec![
+            // Database
+            (
+                database::traits::Error::InvalidID { id: "x".into() }.into(),
+                INVALID_ID_ERROR,
+                Some(json!({ "id": "x" })),
+            ),
+            (
+                database::traits::Error::OutOfBounds {
+                    index: 7,
+                    maximum: 3,
+                }
+                .into(),
+                INVALID_ID_ERROR,
+                Some(json!({ "id": "7" })),
+            ),
+            (
+                database::traits::Error::MissingWord {
+                    word: "zzzzz".into(),
+                }
+                .into(),
+                MISSING_WORD_ERROR,
+                Some(json!({ "word": "zzzzz" })),
+            ),
+            (
+                database::traits::Error::StaleList {
+                    version: 1,
+                    current: 0xabcdef12,
+                }
+                .into(),
+                STALE_WORD_LIST_ERROR,
+                Some(json!({ "version": "00000001", "current": "abcdef12" })),
+            ),
+            (
+                database::traits::Error::UnsupportedLength { length: 7 }.into(),
+                UNSUPPORTED_WORD_LENGTH_ERROR,
+                Some(json!({ "length": 7 })),
+            ),
+            (
+                database::traits::Error::UnknownPool {
+                    pool: "animals".into(),
+                    length: 6,
+                }
+                .into(),
+                UNKNOWN_POOL_ERROR,
+                Some(json!({ "pool": "animals", "length": 6 })),
+            ),
+            (
+                database::traits::Error::NoSource.into(),
+                INTERNAL_ERROR,
+                None,
+            ),
+            (
+                database::traits::Error::EmptyList {
+                    origin: "answers".into(),
+                }
+                .into(),
+                INTERNAL_ERROR,
+                None,
+            ),
+            // Game
+            (
+                game::traits::Error::SessionFinished.into(),
+                SESSION_FINISHED_ERROR,
+                None,
+            ),
+            (
+                game::traits::Error::WrongLength {
+                    expected: 5,
+                    actual: 4,
+                }
+                .into(),
+                WRONG_LENGTH_ERROR,
+                Some(json!({ "expected": 5, "actual": 4 })),
+            ),
+            (
+                game::traits::Error::HardModePlacement {
+                    letter: "b".into(),
+                    position: 3,
+                }
+                .into(),
+                HARD_MODE_ERROR,
+                Some(json!({ "rule": "placement", "letter": "b", "position": 3 })),
+            ),
+            (
+                game::traits::Error::HardModeMissing { letter: "b".into() }.into(),
+                HARD_MODE_ERROR,
+                Some(json!({ "rule": "missing", "letter": "b" })),
+            ),
+            (
+                game::traits::Error::InvalidLetters {
+                    word: "cr4ne".into(),
+                }
+                .into(),
+                INVALID_LETTERS_ERROR,
+                Some(json!({ "word": "cr4ne" })),
+            ),
+            (
+                game::traits::Error::InvalidDate {
+                    date: "2022-02-30".into(),
+                }
+                .into(),
+                INVALID_DATE_ERROR,
+                Some(json!({ "date": "2022-02-30" })),
+            ),
+            (
+                game::traits::Error::FutureDate {
+                    date: "2099-01-01".into(),
+                }
+                .into(),
+                FUTURE_DATE_ERROR,
+                Some(json!({ "date": "2099-01-01" })),
+            ),
+            (
+                game::traits::Error::InvalidUtcOffset { utc_offset: 5000 }.into(),
+                INVALID_UTC_OFFSET_ERROR,
+                Some(json!({ "utc_offset": 5000 })),
+            ),
+            // Session
+            (
+                session::traits::Error::Signing(ring::error::Unspecified).into(),
+                BAD_SIGNATURE_ERROR,
+                None,
+            ),
+            (
+                session::traits::Error::UnknownKey { id: "old".into() }.into(),
+                BAD_SIGNATURE_ERROR,
+                None,
+            ),
+            (
+                session::traits::Error::Expired { expires_at: 42 }.into(),
+                SESSION_EXPIRED_ERROR,
+                Some(json!({ "expires_at": 42 })),
+            ),
+            (
+                session::traits::Error::InvalidFormatting.into(),
+                MALFORMED_SESSION_ERROR,
+                None,
+            ),
+            (
+                session::traits::Error::UnsupportedVersion { version: 1 }.into(),
+                MALFORMED_SESSION_ERROR,
+                None,
+            ),
+            (
+                session::traits::Error::Base64(base64::DecodeError::InvalidLength).into(),
+                MALFORMED_SESSION_ERROR,
+                None,
+            ),
+            // HTTP
+            (
+                Error::NotInGuessList {
+                    guess: "zzzzz".into(),
+                },
+                NOT_IN_GUESS_LIST_ERROR,
+                Some(json!({ "guess": "zzzzz" })),
+            ),
+            (
+                Error::InvalidMaxGuesses { max_guesses: 0 },
+                INVALID_MAX_GUESSES_ERROR,
+                Some(json!({ "max_guesses": 0 })),
+            ),
+            (
+                Error::UnsupportedWordLength { length: 9 },
+                UNSUPPORTED_WORD_LENGTH_ERROR,
+                Some(json!({ "length": 9 })),
+            ),
+            (
+                Error::UnknownLanguage {
+                    language: "xx".into(),
+                },
+                UNKNOWN_LANGUAGE_ERROR,
+                Some(json!({ "language": "xx" })),
+            ),
+            (Error::internal("boom"), INTERNAL_ERROR, None),
+        ];
+
+        let catalogue = catalogue();
+        let mut seen = HashSet::new();
+        for (err, id, details) in cases {
+            assert_eq!(err.id(), id, "{:?}", err);
+            let (status, shape) = catalogue
+                .get(id)
+                .unwrap_or_else(|| panic!("{} is not in the catalogue", id));
+            assert_eq!(err.is_client_error(), *status == 400, "{:?}", err);
+            assert_eq!(err.details(), details, "{:?}", err);
+            match &details {
+                Some(serde_json::Value::Object(fields)) => {
+                    for field in fields.keys() {
+                        assert!(
+                            shape.contains(&format!("\"{}\":", field)),
+                            "{} is not documented for {:?}",
+                            field,
+                            err
+                        );
+                    }
+                }
+                _ => assert_eq!(shape, "none", "{:?}", err),
+            }
+            seen.insert(id);
+
+            let model = models::Error::from(err);
+            assert_eq!(model.id, id);
+            assert_eq!(model.details.is_some(), details.is_some(), "{}", id);
+        }
+        // Every documented error can be returned
+        assert_eq!(
+            seen,
+            catalogue.keys().map(String::as_str).collect::<HashSet<_>>()
+        );
+    }
+}
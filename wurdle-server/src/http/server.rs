@@ -1,10 +1,12 @@
+use super::errors;
 use super::traits;
-use crate::database::traits::Database;
+use crate::database;
+use crate::database::traits::{Database, Word};
 use hyper::Server;
 use log::info;
 use rand::{thread_rng, Rng};
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use ring::digest;
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::str;
 use swagger::{ApiError, EmptyContext, Has, XSpanIdString};
@@ -13,21 +15,21 @@ use wurdle_openapi;
 use wurdle_openapi::models;
 use wurdle_openapi::server;
 
+use crate::game;
 use crate::session;
 
-const MAX_GUESSES: u8 = 6;
-
-const UNKNOWN_ERROR: &str = "abe15c99-eaa4-4fb0-a657-b88430fb8910";
-
 const OPENAPI_YAML: &[u8] = include_bytes!("../../../wurdle-openapi/api/openapi.yaml");
+// Generated seeds stay below 2^53 so they can be represented exactly in JSON by any client
+const MAX_GENERATED_SEED: i64 = 1 << 53;
 
 pub async fn run<T: 'static + Database + Send + Sync + Clone>(
-    db: T,
+    databases: BTreeMap<String, T>,
     sessions: session::manager::SessionManager,
+    daily: game::daily::Daily,
     local_server: bool,
     port: u16,
 ) -> Result<(), traits::Error> {
-    let api = Api::new(db, sessions);
+    let api = Api::new(databases, sessions, daily);
 
     let addr = match local_server {
         true => [127, 0, 0, 1],
@@ -42,69 +44,373 @@ pub async fn run<T: 'static + Database + Send + Sync + Clone>(
     Ok(server.await?)
 }
 
+// Lengths outside of the bounds are rejected even if the database has words for them
+fn parse_word_length(word_length: Option<i32>) -> Result<u8, errors::Error> {
+    let length = match word_length {
+        Some(length) => length,
+        None => return Ok(game::game::DEFAULT_WORD_LENGTH),
+    };
+    u8::try_from(length)
+        .ok()
+        .filter(|length| {
+            (game::game::MIN_WORD_LENGTH..=game::game::MAX_WORD_LENGTH).contains(length)
+        })
+        .ok_or(errors::Error::UnsupportedWordLength { length })
+}
+
+fn parse_max_guesses(max_guesses: Option<i32>) -> Result<u8, errors::Error> {
+    let max_guesses = match max_guesses {
+        Some(max_guesses) => max_guesses,
+        None => return Ok(game::game::DEFAULT_MAX_GUESSES),
+    };
+    u8::try_from(max_guesses)
+        .ok()
+        .filter(|max_guesses| {
+            (game::game::MIN_MAX_GUESSES..=game::game::MAX_MAX_GUESSES).contains(max_guesses)
+        })
+        .ok_or(errors::Error::InvalidMaxGuesses { max_guesses })
+}
+
+// The same seed must give the same word on any platform and with any version of the server,
+// which a random number generator doesn't guarantee
+fn seeded_index(seed: i64, word_count: usize) -> usize {
+    let hash = digest::digest(&digest::SHA256, &seed.to_be_bytes());
+    let mut value = [0; 8];
+    value.copy_from_slice(&hash.as_ref()[..8]);
+    (u64::from_be_bytes(value) % word_count as u64) as usize
+}
+
+// `pool` replaces the deprecated `restricted` flag, all answers are used by default
+fn parse_pool(restricted: Option<bool>, pool: Option<&str>) -> String {
+    match (pool, restricted) {
+        (Some(pool), _) => pool.to_string(),
+        (None, Some(true)) => database::pools::RESTRICTED_POOL.to_string(),
+        (None, _) => database::pools::FULL_POOL.to_string(),
+    }
+}
+
 #[derive(Clone)]
 struct Api<T: Database + Send + Sync + Clone> {
-    db: T,
+    // By language code
+    databases: BTreeMap<String, T>,
     sessions: session::manager::SessionManager,
+    daily: game::daily::Daily,
 }
 
 impl<T: Database + Send + Sync + Clone> Api<T> {
-    fn new(db: T, sessions: session::manager::SessionManager) -> Self {
-        Self { db, sessions }
+    fn new(
+        databases: BTreeMap<String, T>,
+        sessions: session::manager::SessionManager,
+        daily: game::daily::Daily,
+    ) -> Self {
+        Self {
+            databases,
+            sessions,
+            daily,
+        }
+    }
+
+    // The default language is used if none is given
+    fn db(&self, language: Option<&str>) -> Result<&T, errors::Error> {
+        let language = language.unwrap_or(database::languages::DEFAULT_LANGUAGE);
+        self.databases
+            .get(language)
+            .ok_or_else(|| errors::Error::UnknownLanguage {
+                language: language.to_string(),
+            })
+    }
+
+    // Words given by clients are compared the same way as the words of the lists
+    fn parse_word(&self, db: &T, word: &str) -> Result<String, errors::Error> {
+        Ok(game::letters::parse(word, db.folds_accents())?)
     }
 
+    // `word_length` is only checked when given, the id is enough to find the word
     fn session_for_word_id(
         &self,
         word_id: &str,
-    ) -> Result<
-        Result<wurdle_openapi::models::SessionStart, wurdle_openapi::models::Error>,
-        wurdle_openapi::models::Error,
-    > {
-        match self.db.word_for_id(&word_id) {
-            Ok(_) => (),
-            Err(err) => {
-                // TODO: WRONG
-                return Err(wurdle_openapi::models::Error {
-                    id: UNKNOWN_ERROR.to_string(),
-                    message: format!("{}", err),
-                    details: None,
-                });
-            }
-        };
-        Ok(self.make_session(word_id))
+        language: Option<&str>,
+        word_length: Option<u8>,
+        hard_mode: bool,
+        max_guesses: u8,
+    ) -> Result<wurdle_openapi::models::SessionStart, errors::Error> {
+        let word = self.db(language)?.word_for_id(word_id)?;
+        if word_length
+            .is_some_and(|length| usize::from(length) != game::letters::length(&word.word))
+        {
+            return Err(errors::Error::InvalidID {
+                id: word_id.to_string(),
+            });
+        }
+        self.make_session(&self.new_session(&word, language, hard_mode, max_guesses))
+    }
+
+    fn new_session(
+        &self,
+        word: &Word,
+        language: Option<&str>,
+        hard_mode: bool,
+        max_guesses: u8,
+    ) -> session::session::Session {
+        let mut session = session::session::Session::new(
+            word.word_id.as_str(),
+            game::letters::length(&word.word) as u8,
+            hard_mode,
+            max_guesses,
+            self.sessions.ttl(),
+        );
+        session.language = language
+            .filter(|language| *language != database::languages::DEFAULT_LANGUAGE)
+            .map(str::to_string);
+        session.list_version = Some(word.version);
+        session
+    }
+
+    // The id tells which word to guess, it would defeat encrypted sessions
+    fn visible_word_id(&self, session: &session::session::Session) -> Option<String> {
+        match self.sessions.mode() {
+            session::manager::Mode::Signed => Some(session.word_id.clone()),
+            session::manager::Mode::Encrypted => None,
+        }
     }
 
     fn make_session(
         &self,
-        word_id: &str,
-    ) -> Result<wurdle_openapi::models::SessionStart, wurdle_openapi::models::Error> {
-        let session = session::session::Session::new(word_id);
-        match self.sessions.serialize(&session) {
-            Ok(session_id) => Ok(wurdle_openapi::models::SessionStart {
-                session_id,
-                word_id: word_id.to_string(),
-            }),
-            Err(err) => Err(wurdle_openapi::models::Error {
-                id: UNKNOWN_ERROR.to_string(),
-                message: format!("{}", err),
-                details: None,
-            }),
+        session: &session::session::Session,
+    ) -> Result<wurdle_openapi::models::SessionStart, errors::Error> {
+        let session_id = self
+            .sessions
+            .serialize(session)
+            .map_err(errors::Error::internal)?;
+        Ok(wurdle_openapi::models::SessionStart {
+            session_id,
+            word_id: self.visible_word_id(session),
+            puzzle_number: session.puzzle_number.map(Into::into),
+            seed: None,
+        })
+    }
+
+    fn get_session(&self, session_id: &str) -> Result<session::session::Session, errors::Error> {
+        Ok(self.sessions.deserialize(session_id)?)
+    }
+
+    fn id_session(
+        &self,
+        payload: &models::StartWithIdPayload,
+    ) -> Result<wurdle_openapi::models::SessionStart, errors::Error> {
+        self.session_for_word_id(
+            payload.word_id.as_str(),
+            payload.language.as_deref(),
+            payload
+                .word_length
+                .map(|length| parse_word_length(Some(length)))
+                .transpose()?,
+            payload.hard_mode.unwrap_or(false),
+            parse_max_guesses(payload.max_guesses)?,
+        )
+    }
+
+    fn random_session(
+        &self,
+        payload: &models::StartRandomPayload,
+    ) -> Result<wurdle_openapi::models::SessionStart, errors::Error> {
+        // Sessions are always seeded so that any game can be reproduced
+        let seed = payload
+            .seed
+            .unwrap_or_else(|| thread_rng().gen_range(0..MAX_GENERATED_SEED));
+
+        let db = self.db(payload.language.as_deref())?;
+        let word_length = parse_word_length(payload.word_length)?;
+        let pool = parse_pool(payload.restricted, payload.pool.as_deref());
+        let word_count = db.word_count(word_length, &pool)?;
+        // The index is within the pool, not the full list
+        let word = db.word_for_index(word_length, &pool, seeded_index(seed, word_count))?;
+
+        let mut session = self.new_session(
+            &word,
+            payload.language.as_deref(),
+            payload.hard_mode.unwrap_or(false),
+            parse_max_guesses(payload.max_guesses)?,
+        );
+        session.pool = Some(pool);
+
+        let start = self.make_session(&session)?;
+        Ok(wurdle_openapi::models::SessionStart {
+            seed: Some(seed),
+            ..start
+        })
+    }
+
+    fn word_session(
+        &self,
+        payload: &models::StartWithWordPayload,
+    ) -> Result<wurdle_openapi::models::SessionStart, errors::Error> {
+        let db = self.db(payload.language.as_deref())?;
+        let word = db.word_exists(&self.parse_word(db, &payload.word)?)?;
+        self.make_session(&self.new_session(
+            &word,
+            payload.language.as_deref(),
+            payload.hard_mode.unwrap_or(false),
+            parse_max_guesses(payload.max_guesses)?,
+        ))
+    }
+
+    // Everyone gets the same word for a given date, whatever their time zone
+    fn daily_session(
+        &self,
+        payload: &models::StartDailyPayload,
+    ) -> Result<wurdle_openapi::models::SessionStart, errors::Error> {
+        let today =
+            game::daily::Date::today(session::session::now(), payload.utc_offset.unwrap_or(0))?;
+        let date = match &payload.date {
+            Some(date) => game::daily::Date::parse(date)?,
+            None => today,
+        };
+        if date > today {
+            return Err(errors::Error::FutureDate {
+                date: date.to_string(),
+            });
         }
+        let puzzle_number = date.puzzle_number()?;
+
+        let db = self.db(payload.language.as_deref())?;
+        let word_length = parse_word_length(payload.word_length)?;
+        let word_count = db.word_count(word_length, database::pools::FULL_POOL)?;
+        let word = db.word_for_index(
+            word_length,
+            database::pools::FULL_POOL,
+            self.daily.index(puzzle_number, word_length, word_count),
+        )?;
+
+        let mut session = self.new_session(
+            &word,
+            payload.language.as_deref(),
+            payload.hard_mode.unwrap_or(false),
+            parse_max_guesses(payload.max_guesses)?,
+        );
+        session.puzzle_number = Some(puzzle_number);
+        self.make_session(&session)
     }
 
-    fn get_session(
+    fn guess(
         &self,
-        session_id: &str,
-    ) -> Result<session::session::Session, wurdle_openapi::models::Error> {
-        match self.sessions.deserialize(&session_id) {
-            Ok(session) => Ok(session),
-            // TODO: wrong
-            Err(err) => Err(wurdle_openapi::models::Error {
-                id: UNKNOWN_ERROR.to_string(),
-                message: format!("{}", err),
-                details: None,
-            }),
+        payload: &models::GuessPayload,
+    ) -> Result<wurdle_openapi::models::GuessReply, errors::Error> {
+        let session = self.get_session(&payload.session_id)?;
+        game::game::Game::check_in_progress(&session)?;
+
+        let db = self.db(session.language.as_deref())?;
+        let word =
+            db.word_for_session(session.word_length, &session.word_id, session.list_version)?;
+        let guess = self.parse_word(db, &payload.guess)?;
+        let game = game::game::Game::new(&word.word);
+        game.check_length(&guess)?;
+
+        if !db.guess_exists(&guess)? {
+            return Err(errors::Error::NotInGuessList { guess });
         }
+
+        let turn = game.guess(session, &guess)?;
+
+        let session_id = self
+            .sessions
+            .serialize(&turn.session)
+            .map_err(errors::Error::internal)?;
+
+        Ok(wurdle_openapi::models::GuessReply {
+            guess_number: turn.session.used_guesses().into(),
+            status: turn.session.status.to_string(),
+            result: turn
+                .result
+                .iter()
+                .map(|result| result.to_string())
+                .collect(),
+            session_id,
+        })
+    }
+
+    fn session_info(
+        &self,
+        session_id: &str,
+    ) -> Result<wurdle_openapi::models::InfoReply, errors::Error> {
+        let session = self.get_session(session_id)?;
+        Ok(wurdle_openapi::models::InfoReply {
+            word_id: self.visible_word_id(&session),
+            word_length: session.word_length.into(),
+            language: session
+                .language
+                .clone()
+                .unwrap_or_else(|| database::languages::DEFAULT_LANGUAGE.to_string()),
+            status: session.status.to_string(),
+            guess_number: session.used_guesses().into(),
+            max_guesses: session.max_guesses.into(),
+            puzzle_number: session.puzzle_number.map(Into::into),
+            pool: session.pool.clone(),
+            hard_mode: session.hard_mode,
+            board: session
+                .history
+                .iter()
+                .map(|guess| wurdle_openapi::models::BoardEntry {
+                    guess: guess.word.clone(),
+                    result: guess
+                        .result
+                        .iter()
+                        .map(|result| result.to_string())
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+
+    // Pools are called packs in the API
+    fn packs(
+        &self,
+        language: Option<&str>,
+    ) -> Result<wurdle_openapi::models::PacksList, errors::Error> {
+        Ok(wurdle_openapi::models::PacksList {
+            packs: self
+                .db(language)?
+                .pools()?
+                .into_iter()
+                .map(|pool| wurdle_openapi::models::Pack {
+                    name: pool.name,
+                    description: pool.description,
+                    word_counts: pool
+                        .word_counts
+                        .into_iter()
+                        .map(
+                            |(word_length, count)| wurdle_openapi::models::PackWordCount {
+                                word_length: word_length.into(),
+                                count: i32::try_from(count).unwrap_or(i32::MAX),
+                            },
+                        )
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+
+    fn languages(&self) -> Result<wurdle_openapi::models::LanguagesList, errors::Error> {
+        Ok(wurdle_openapi::models::LanguagesList {
+            languages: self
+                .databases
+                .iter()
+                .map(|(code, db)| {
+                    // Lengths which can be played are the ones with answers
+                    let word_lengths = db
+                        .pools()?
+                        .into_iter()
+                        .find(|pool| pool.name == database::pools::FULL_POOL)
+                        .map(|pool| pool.word_counts.into_keys().map(Into::into).collect())
+                        .unwrap_or_default();
+                    Ok(wurdle_openapi::models::Language {
+                        code: code.clone(),
+                        fold_accents: db.folds_accents(),
+                        word_lengths,
+                    })
+                })
+                .collect::<Result<_, errors::Error>>()?,
+        })
     }
 }
 
@@ -123,11 +429,7 @@ where
         Ok(match str::from_utf8(OPENAPI_YAML) {
             Ok(string) => wurdle_openapi::GetOpenAPIResponse::TheDefinition(string.to_string()),
             Err(err) => {
-                wurdle_openapi::GetOpenAPIResponse::ServerError(wurdle_openapi::models::Error {
-                    id: UNKNOWN_ERROR.to_string(),
-                    message: format!("{}", err),
-                    details: None,
-                })
+                wurdle_openapi::GetOpenAPIResponse::ServerError(errors::Error::internal(err).into())
             }
         })
     }
@@ -144,124 +446,12 @@ where
             context.get().0.clone()
         );
 
-        let mut session = match self.get_session(&payload.session_id) {
-            Ok(session) => session,
-            Err(err) => {
-                // TODO: wrong
-                return Ok(wurdle_openapi::DoGuessResponse::InvalidGuess(err));
-            }
-        };
-        Ok(match session.status {
-            session::session::Status::InProgress { used_guesses } => {
-                let exists = match self.db.guess_exists(&payload.guess) {
-                    Ok(exists) => exists,
-                    Err(err) => {
-                        // TODO: wrong
-                        return Ok(wurdle_openapi::DoGuessResponse::ServerError(
-                            wurdle_openapi::models::Error {
-                                id: UNKNOWN_ERROR.to_string(),
-                                message: format!("{}", err),
-                                details: None,
-                            },
-                        ));
-                    }
-                };
-
-                if !exists {
-                    return Ok(wurdle_openapi::DoGuessResponse::InvalidGuess(
-                        wurdle_openapi::models::Error {
-                            id: UNKNOWN_ERROR.to_string(),
-                            message: "word not on the list".to_string(),
-                            details: None,
-                        },
-                    ));
-                }
-
-                let word = match self.db.word_for_id(&session.word_id) {
-                    Ok(word) => word,
-                    Err(err) => {
-                        // TODO: WRONG
-                        return Ok(wurdle_openapi::DoGuessResponse::InvalidGuess(
-                            wurdle_openapi::models::Error {
-                                id: UNKNOWN_ERROR.to_string(),
-                                message: format!("{}", err),
-                                details: None,
-                            },
-                        ));
-                    }
-                };
-
-                let used_guesses = used_guesses + 1;
-
-                let mut result: Vec<String> = vec![];
-                if word.word == payload.guess {
-                    session.status = session::session::Status::Won { used_guesses };
-                    result = vec![
-                        "valid".to_string(),
-                        "valid".to_string(),
-                        "valid".to_string(),
-                        "valid".to_string(),
-                        "valid".to_string(),
-                    ];
-                } else {
-                    session.status = if used_guesses >= MAX_GUESSES {
-                        session::session::Status::Failed
-                    } else {
-                        session::session::Status::InProgress { used_guesses }
-                    };
-                    let word_slice = word.word.as_bytes();
-
-                    let mut letters_lookup: HashMap<u8, u32> = HashMap::new();
-                    for letter in word_slice.iter() {
-                        let amount = letters_lookup.entry(*letter).or_insert(0);
-                        *amount += 1;
-                    }
-
-                    for (expected, received) in
-                        word_slice.iter().zip(payload.guess.as_bytes().iter())
-                    {
-                        if expected == received {
-                            result.push("valid".to_string())
-                        } else {
-                            let indicator = match letters_lookup.entry(*received) {
-                                Entry::Occupied(mut count) if count.get() > &0 => {
-                                    count.insert(*count.get() - 1);
-                                    "wrong_place"
-                                }
-                                Entry::Occupied(_) | Entry::Vacant(_) => "wrong",
-                            };
-                            result.push(indicator.to_string())
-                        }
-                    }
-                }
-
-                let session_id = match self.sessions.serialize(&session) {
-                    Ok(session_id) => session_id,
-                    Err(err) => {
-                        return Ok(wurdle_openapi::DoGuessResponse::ServerError(
-                            wurdle_openapi::models::Error {
-                                id: UNKNOWN_ERROR.to_string(),
-                                message: format!("{}", err),
-                                details: None,
-                            },
-                        ))
-                    }
-                };
-
-                wurdle_openapi::DoGuessResponse::ValidGuess(wurdle_openapi::models::GuessReply {
-                    guess_number: used_guesses.into(),
-                    status: session.status.to_string(),
-                    result,
-                    session_id,
-                })
-            }
-            session::session::Status::Failed | session::session::Status::Won { .. } => {
-                wurdle_openapi::DoGuessResponse::InvalidGuess(wurdle_openapi::models::Error {
-                    id: UNKNOWN_ERROR.to_string(),
-                    message: "session is already finished".to_string(),
-                    details: None,
-                })
+        Ok(match self.guess(&payload) {
+            Ok(reply) => wurdle_openapi::DoGuessResponse::ValidGuess(reply),
+            Err(err) if err.is_client_error() => {
+                wurdle_openapi::DoGuessResponse::InvalidGuess(err.into())
             }
+            Err(err) => wurdle_openapi::DoGuessResponse::ServerError(err.into()),
         })
     }
 
@@ -277,25 +467,13 @@ where
             context.get().0.clone()
         );
 
-        let session = match self.get_session(&session_id) {
-            Ok(session) => session,
-            Err(err) => {
-                // TODO: wrong
-                return Ok(wurdle_openapi::GetSessionInfoResponse::UsingAnInvalidSession(err));
+        Ok(match self.session_info(&session_id) {
+            Ok(reply) => wurdle_openapi::GetSessionInfoResponse::SuccessfulOperation(reply),
+            Err(err) if err.is_client_error() => {
+                wurdle_openapi::GetSessionInfoResponse::UsingAnInvalidSession(err.into())
             }
-        };
-        Ok(wurdle_openapi::GetSessionInfoResponse::SuccessfulOperation(
-            wurdle_openapi::models::InfoReply {
-                word_id: session.word_id.to_string(),
-                status: session.status.to_string(),
-                guess_number: match session.status {
-                    session::session::Status::InProgress { used_guesses }
-                    | session::session::Status::Won { used_guesses } => used_guesses,
-                    session::session::Status::Failed => MAX_GUESSES,
-                }
-                .into(),
-            },
-        ))
+            Err(err) => wurdle_openapi::GetSessionInfoResponse::ServerError(err.into()),
+        })
     }
 
     async fn start_random(
@@ -306,29 +484,12 @@ where
         let context = context.clone();
         info!("start_random() - X-Span-ID: {:?}", context.get().0.clone());
 
-        let mut rng = thread_rng();
-
-        let word_length = match self.db.word_length(payload.restricted) {
-            Ok(word_length) => word_length,
-            Err(err) => {
-                return Ok(wurdle_openapi::StartRandomResponse::ServerError(
-                    wurdle_openapi::models::Error {
-                        id: UNKNOWN_ERROR.to_string(),
-                        message: format!("{}", err),
-                        details: None,
-                    },
-                ))
+        Ok(match self.random_session(&payload) {
+            Ok(session) => wurdle_openapi::StartRandomResponse::SessionCreatedSuccessfully(session),
+            Err(err) if err.is_client_error() => {
+                wurdle_openapi::StartRandomResponse::InvalidParameters(err.into())
             }
-        };
-        let n: usize = rng.gen_range(0..word_length);
-        Ok(match self.session_for_word_id(n.to_string().as_str()) {
-            Ok(inner) => match inner {
-                Ok(session) => {
-                    wurdle_openapi::StartRandomResponse::SessionCreatedSuccessfully(session)
-                }
-                Err(err) => wurdle_openapi::StartRandomResponse::ServerError(err),
-            },
-            Err(err) => wurdle_openapi::StartRandomResponse::ServerError(err),
+            Err(err) => wurdle_openapi::StartRandomResponse::ServerError(err.into()),
         })
     }
 
@@ -344,14 +505,33 @@ where
             context.get().0.clone()
         );
 
-        Ok(match self.session_for_word_id(payload.word_id.as_str()) {
-            Ok(inner) => match inner {
-                Ok(session) => {
-                    wurdle_openapi::StartWithIDResponse::SessionCreatedSuccessfully(session)
-                }
-                Err(err) => wurdle_openapi::StartWithIDResponse::ServerError(err),
-            },
-            Err(err) => wurdle_openapi::StartWithIDResponse::InvalidID(err),
+        Ok(match self.id_session(&payload) {
+            Ok(session) => wurdle_openapi::StartWithIDResponse::SessionCreatedSuccessfully(session),
+            Err(err) if err.is_client_error() => {
+                wurdle_openapi::StartWithIDResponse::InvalidID(err.into())
+            }
+            Err(err) => wurdle_openapi::StartWithIDResponse::ServerError(err.into()),
+        })
+    }
+
+    async fn start_daily(
+        &self,
+        payload: models::StartDailyPayload,
+        context: &C,
+    ) -> Result<wurdle_openapi::StartDailyResponse, ApiError> {
+        let context = context.clone();
+        info!(
+            "start_daily({:?}) - X-Span-ID: {:?}",
+            payload,
+            context.get().0.clone()
+        );
+
+        Ok(match self.daily_session(&payload) {
+            Ok(session) => wurdle_openapi::StartDailyResponse::SessionCreatedSuccessfully(session),
+            Err(err) if err.is_client_error() => {
+                wurdle_openapi::StartDailyResponse::InvalidParameters(err.into())
+            }
+            Err(err) => wurdle_openapi::StartDailyResponse::ServerError(err.into()),
         })
     }
 
@@ -367,77 +547,830 @@ where
             context.get().0.clone()
         );
 
-        let word = match self.db.word_exists(payload.word.as_str()) {
-            Ok(word) => word,
-            Err(err) => {
-                return Ok(wurdle_openapi::StartWithWordResponse::InvalidWord(
-                    wurdle_openapi::models::Error {
-                        id: UNKNOWN_ERROR.to_string(),
-                        message: format!("{}", err),
-                        details: None,
-                    },
-                ))
-            }
-        };
-        Ok(match self.make_session(word.word_id.as_str()) {
+        Ok(match self.word_session(&payload) {
             Ok(session) => {
                 wurdle_openapi::StartWithWordResponse::SessionCreatedSuccessfully(session)
             }
-            Err(err) => wurdle_openapi::StartWithWordResponse::ServerError(err),
+            Err(err) if err.is_client_error() => {
+                wurdle_openapi::StartWithWordResponse::InvalidWord(err.into())
+            }
+            Err(err) => wurdle_openapi::StartWithWordResponse::ServerError(err.into()),
         })
     }
 
     async fn get_allowed_words(
         &self,
+        word_length: Option<i32>,
+        language: Option<String>,
         context: &C,
     ) -> Result<wurdle_openapi::GetAllowedWordsResponse, ApiError> {
         let context = context.clone();
         info!(
-            "get_allowed_words() - X-Span-ID: {:?}",
+            "get_allowed_words({:?}, {:?}) - X-Span-ID: {:?}",
+            word_length,
+            language,
             context.get().0.clone()
         );
 
-        Ok(match self.db.allowed_words() {
-            Ok(words) => {
-                wurdle_openapi::GetAllowedWordsResponse::SuccessfulOperation(models::WordsList {
-                    words,
-                })
-            }
-            Err(err) => wurdle_openapi::GetAllowedWordsResponse::ServerError(
-                wurdle_openapi::models::Error {
-                    id: UNKNOWN_ERROR.to_string(),
-                    message: format!("{}", err),
-                    details: None,
-                },
-            ),
-        })
+        Ok(
+            match parse_word_length(word_length).and_then(|word_length| {
+                Ok(self.db(language.as_deref())?.allowed_words(word_length)?)
+            }) {
+                Ok(words) => wurdle_openapi::GetAllowedWordsResponse::SuccessfulOperation(
+                    models::WordsList { words },
+                ),
+                Err(err) if err.is_client_error() => {
+                    wurdle_openapi::GetAllowedWordsResponse::InvalidParameters(err.into())
+                }
+                Err(err) => wurdle_openapi::GetAllowedWordsResponse::ServerError(err.into()),
+            },
+        )
     }
 
     async fn get_answer_words(
         &self,
-        restricted: bool,
+        restricted: Option<bool>,
+        word_length: Option<i32>,
+        pool: Option<String>,
+        language: Option<String>,
         context: &C,
     ) -> Result<wurdle_openapi::GetAnswerWordsResponse, ApiError> {
         let context = context.clone();
         info!(
-            "get_allowed_words({:?}) - X-Span-ID: {:?}",
+            "get_allowed_words({:?}, {:?}, {:?}, {:?}) - X-Span-ID: {:?}",
             restricted,
+            word_length,
+            pool,
+            language,
             context.get().0.clone()
         );
 
-        Ok(match self.db.answer_words(restricted) {
-            Ok(words) => {
-                wurdle_openapi::GetAnswerWordsResponse::SuccessfulOperation(models::WordsList {
-                    words,
-                })
-            }
-            Err(err) => {
-                wurdle_openapi::GetAnswerWordsResponse::ServerError(wurdle_openapi::models::Error {
-                    id: UNKNOWN_ERROR.to_string(),
-                    message: format!("{}", err),
-                    details: None,
-                })
+        let pool = parse_pool(restricted, pool.as_deref());
+        Ok(
+            match parse_word_length(word_length).and_then(|word_length| {
+                Ok(self
+                    .db(language.as_deref())?
+                    .answer_words(word_length, &pool)?)
+            }) {
+                Ok(words) => {
+                    wurdle_openapi::GetAnswerWordsResponse::SuccessfulOperation(models::WordsList {
+                        words,
+                    })
+                }
+                Err(err) if err.is_client_error() => {
+                    wurdle_openapi::GetAnswerWordsResponse::InvalidParameters(err.into())
+                }
+                Err(err) => wurdle_openapi::GetAnswerWordsResponse::ServerError(err.into()),
+            },
+        )
+    }
+
+    async fn get_packs(
+        &self,
+        language: Option<String>,
+        context: &C,
+    ) -> Result<wurdle_openapi::GetPacksResponse, ApiError> {
+        let context = context.clone();
+        info!(
+            "get_packs({:?}) - X-Span-ID: {:?}",
+            language,
+            context.get().0.clone()
+        );
+
+        Ok(match self.packs(language.as_deref()) {
+            Ok(packs) => wurdle_openapi::GetPacksResponse::SuccessfulOperation(packs),
+            Err(err) if err.is_client_error() => {
+                wurdle_openapi::GetPacksResponse::InvalidParameters(err.into())
             }
+            Err(err) => wurdle_openapi::GetPacksResponse::ServerError(err.into()),
         })
     }
+
+    async fn get_languages(
+        &self,
+        context: &C,
+    ) -> Result<wurdle_openapi::GetLanguagesResponse, ApiError> {
+        let context = context.clone();
+        info!("get_languages() - X-Span-ID: {:?}", context.get().0.clone());
+
+        Ok(match self.languages() {
+            Ok(languages) => wurdle_openapi::GetLanguagesResponse::SuccessfulOperation(languages),
+            Err(err) => wurdle_openapi::GetLanguagesResponse::ServerError(err.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::http::{Database as ListDatabase, Fetcher};
+    use crate::database::ids::WordIds;
+    use crate::database::languages::Language;
+    use crate::database::pools::Pool;
+    use crate::database::source::Source;
+    use crate::session::keys::Key;
+    use crate::session::manager::{Mode, SessionManager};
+    use serde_json::json;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    static ANSWERS: &str = "abbey\nbimbo\ncrane\ndrama\neerie\nfifty\ngeese\nhumph\nkebab\nlever\n\
+                            mamma\nnanny\nopera\npudgy\nqueen\nsloth\nthose\nuncle\nvivid\nwakes\n\
+                            banana\ncrayon\n";
+    static ALLOWED: &str = "aback\nbabes\nbbbxx\ntabes\nzonal\n";
+
+    async fn api() -> Api<ListDatabase> {
+        api_with_mode(Mode::Signed).await
+    }
+
+    async fn api_with_mode(mode: Mode) -> Api<ListDatabase> {
+        let language = Language {
+            code: database::languages::DEFAULT_LANGUAGE.to_string(),
+            answers: vec![Source::Embedded {
+                name: "answers",
+                body: ANSWERS,
+            }],
+            allowed: vec![Source::Embedded {
+                name: "allowed",
+                body: ALLOWED,
+            }],
+            fold_accents: false,
+        };
+        let pools = [
+            Pool::Sample {
+                name: database::pools::RESTRICTED_POOL.to_string(),
+                description: "Sample".to_string(),
+                size: 5,
+                seed: database::pools::DEFAULT_RESTRICTED_SEED.to_string(),
+            },
+            // Not an answer: zebra
+            Pool::Curated {
+                name: "animals".to_string(),
+                description: "Animals".to_string(),
+                source: Source::Embedded {
+                    name: "animals",
+                    body: "sloth\nzebra\ncrayon\n",
+                },
+            },
+        ];
+        let ids = WordIds::new(b"test secret").unwrap();
+        let fetcher = Fetcher::new(Duration::from_secs(1), 0, Duration::ZERO).unwrap();
+        let db = ListDatabase::new(&language, &pools, &ids, &fetcher)
+            .await
+            .unwrap();
+        let keys = [Key::new("test", "dGVzdCBzZWNyZXQ=").unwrap()];
+        Api::new(
+            BTreeMap::from([(language.code, db)]),
+            SessionManager::new(&keys, mode, None).unwrap(),
+            game::daily::Daily::new("test"),
+        )
+    }
+
+    fn random_payload(pool: Option<&str>, seed: Option<i64>) -> models::StartRandomPayload {
+        models::StartRandomPayload {
+            language: None,
+            restricted: None,
+            pool: pool.map(str::to_string),
+            seed,
+            word_length: None,
+            hard_mode: None,
+            max_guesses: None,
+        }
+    }
+
+    // Word of the session, through the id given to the client
+    fn word(api: &Api<ListDatabase>, start: &models::SessionStart) -> String {
+        api.db(None)
+            .unwrap()
+            .word_for_id(start.word_id.as_deref().unwrap())
+            .unwrap()
+            .word
+    }
+
+    #[test]
+    fn seeded_index_is_pinned() {
+        let cases = [
+            (0, 2315, 610),
+            (0, 500, 350),
+            (1, 2315, 1945),
+            (42, 2315, 1073),
+            (42, 500, 438),
+            (-1, 2315, 1225),
+            (MAX_GENERATED_SEED, 2315, 1296),
+        ];
+        for (seed, word_count, index) in cases {
+            assert_eq!(seeded_index(seed, word_count), index, "seed {}", seed);
+        }
+    }
+
+    #[tokio::test]
+    async fn seeded_words_are_pinned() {
+        let api = api().await;
+        let cases = [(0, "mamma"), (1, "mamma"), (42, "vivid"), (-1, "fifty")];
+        for (seed, expected) in cases {
+            let start = api
+                .random_session(&random_payload(None, Some(seed)))
+                .unwrap();
+            assert_eq!(start.seed, Some(seed));
+            assert_eq!(word(&api, &start), expected, "seed {}", seed);
+        }
+    }
+
+    fn id_payload(word_id: &str) -> models::StartWithIdPayload {
+        models::StartWithIdPayload {
+            word_id: word_id.to_string(),
+            language: None,
+            word_length: None,
+            hard_mode: None,
+            max_guesses: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn only_opaque_ids_start_sessions() {
+        let api = api().await;
+        let word = api.db(None).unwrap().word_exists("crane").unwrap();
+        let start = api.id_session(&id_payload(&word.word_id)).unwrap();
+        assert_eq!(start.word_id, Some(word.word_id.clone()));
+
+        // Positions in the list would let anyone map ids to words
+        for id in ["0", "1", "19"] {
+            assert!(matches!(
+                api.id_session(&id_payload(id)),
+                Err(errors::Error::InvalidID { .. })
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn sessions_without_list_version_use_positions() {
+        let api = api().await;
+        let db = api.db(None).unwrap();
+        // Lists used to start with an empty line
+        let word = db.word_for_session(5, "3", None).unwrap();
+        assert_eq!(word.word, "crane");
+        assert_eq!(db.word_for_session(5, "1", None).unwrap().word, "abbey");
+        assert!(matches!(
+            db.word_for_session(5, "0", None),
+            Err(database::traits::Error::InvalidID { .. })
+        ));
+        assert!(db.word_for_session(5, &word.word_id, None).is_err());
+        assert_eq!(
+            db.word_for_session(5, &word.word_id, Some(word.version))
+                .unwrap()
+                .word,
+            "crane"
+        );
+        assert!(matches!(
+            db.word_for_session(5, "2", Some(word.version)),
+            Err(database::traits::Error::InvalidID { .. })
+        ));
+        assert!(matches!(
+            db.word_for_session(5, "unknown", Some(word.version ^ 1)),
+            Err(database::traits::Error::StaleList { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn random_words_belong_to_the_pool() {
+        let api = api().await;
+        let db = api.db(None).unwrap();
+        let cases = [
+            (None, 5, database::pools::FULL_POOL),
+            (
+                Some(database::pools::RESTRICTED_POOL),
+                5,
+                database::pools::RESTRICTED_POOL,
+            ),
+            (Some("animals"), 5, "animals"),
+            (Some("animals"), 6, "animals"),
+        ];
+        for (pool, word_length, expected_pool) in cases {
+            let words = db
+                .answer_words(word_length, expected_pool)
+                .unwrap()
+                .into_iter()
+                .collect::<HashSet<_>>();
+            let mut drawn = HashSet::new();
+            for seed in 0..200 {
+                let payload = models::StartRandomPayload {
+                    word_length: Some(word_length.into()),
+                    ..random_payload(pool, Some(seed))
+                };
+                let start = api.random_session(&payload).unwrap();
+                let session = api.get_session(&start.session_id).unwrap();
+                assert_eq!(session.pool.as_deref(), Some(expected_pool));
+
+                let word = word(&api, &start);
+                assert!(
+                    words.contains(&word),
+                    "{} is not in {}",
+                    word,
+                    expected_pool
+                );
+                assert_eq!(Some(db.word_exists(&word).unwrap().word_id), start.word_id);
+                drawn.insert(word);
+            }
+            // Every word of the pool can be drawn, not only the first ones
+            if words.len() <= 20 {
+                assert_eq!(drawn, words, "pool {}", expected_pool);
+            }
+        }
+        assert_eq!(db.answer_words(5, "animals").unwrap(), ["sloth"]);
+        assert_eq!(
+            db.answer_words(5, database::pools::RESTRICTED_POOL)
+                .unwrap()
+                .len(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn deprecated_restricted_flag_uses_the_restricted_pool() {
+        let api = api().await;
+        let payload = models::StartRandomPayload {
+            restricted: Some(true),
+            ..random_payload(None, Some(7))
+        };
+        let session = api
+            .get_session(&api.random_session(&payload).unwrap().session_id)
+            .unwrap();
+        assert_eq!(
+            session.pool.as_deref(),
+            Some(database::pools::RESTRICTED_POOL)
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_pools_are_rejected() {
+        let api = api().await;
+        for (pool, word_length) in [("unknown", 5), (database::pools::RESTRICTED_POOL, 7)] {
+            let payload = models::StartRandomPayload {
+                word_length: Some(word_length),
+                ..random_payload(Some(pool), None)
+            };
+            assert!(api.random_session(&payload).is_err(), "{}", pool);
+        }
+    }
+
+    struct Context(XSpanIdString);
+
+    impl Has<XSpanIdString> for Context {
+        fn get(&self) -> &XSpanIdString {
+            &self.0
+        }
+
+        fn get_mut(&mut self) -> &mut XSpanIdString {
+            &mut self.0
+        }
+
+        fn set(&mut self, value: XSpanIdString) {
+            self.0 = value
+        }
+    }
+
+    fn word_payload(word: &str) -> models::StartWithWordPayload {
+        models::StartWithWordPayload {
+            word: word.to_string(),
+            language: None,
+            hard_mode: None,
+            max_guesses: None,
+        }
+    }
+
+    fn guess_payload(session_id: &str, guess: &str) -> models::GuessPayload {
+        models::GuessPayload {
+            session_id: session_id.to_string(),
+            guess: guess.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn words_ignore_case_and_whitespace() {
+        let api = api().await;
+        let db = api.db(None).unwrap();
+        let crane = db.word_exists("crane").unwrap();
+        assert_eq!(db.word_exists("  Crane\n").unwrap().word_id, crane.word_id);
+        assert!(db.guess_exists(" ABACK ").unwrap());
+        assert!(!db.guess_exists("cr4ne").unwrap());
+
+        let start = api.word_session(&word_payload(" CRANE\n")).unwrap();
+        assert_eq!(start.word_id, Some(crane.word_id));
+        let reply = api
+            .guess(&guess_payload(&start.session_id, "\tSloth "))
+            .unwrap();
+        assert_eq!(reply.guess_number, 1.0);
+        let info = api.session_info(&reply.session_id).unwrap();
+        assert_eq!(info.board[0].guess, "sloth");
+    }
+
+    #[tokio::test]
+    async fn words_must_be_letters() {
+        let api = api().await;
+        assert!(matches!(
+            api.word_session(&word_payload("cr4ne")),
+            Err(errors::Error::InvalidLetters { .. })
+        ));
+        let start = api.word_session(&word_payload("crane")).unwrap();
+        for guess in ["cr4ne", "cr ne", "", "crane!"] {
+            assert!(matches!(
+                api.guess(&guess_payload(&start.session_id, guess)),
+                Err(errors::Error::InvalidLetters { word }) if word == guess
+            ));
+        }
+
+        let context = Context(XSpanIdString::default());
+        let response = wurdle_openapi::Api::do_guess(
+            &api,
+            guess_payload(&start.session_id, "cr4ne"),
+            &context,
+        )
+        .await
+        .unwrap();
+        match response {
+            wurdle_openapi::DoGuessResponse::InvalidGuess(err) => {
+                assert_eq!(err.id, errors::INVALID_LETTERS_ERROR)
+            }
+            _ => panic!("expected an invalid guess"),
+        }
+    }
+
+    #[tokio::test]
+    async fn hard_mode_errors_tell_which_rule_was_broken() {
+        let api = api().await;
+        let payload = |hard_mode| models::StartWithWordPayload {
+            hard_mode: Some(hard_mode),
+            ..word_payload("abbey")
+        };
+        let start = api.word_session(&payload(true)).unwrap();
+        let session_id = api
+            .guess(&guess_payload(&start.session_id, "babes"))
+            .unwrap()
+            .session_id;
+
+        // `babes` places b and e and reveals two b
+        let cases = [
+            (
+                "kebab",
+                json!({ "rule": "placement", "letter": "e", "position": 4 }),
+            ),
+            ("tabes", json!({ "rule": "missing", "letter": "b" })),
+        ];
+        for (guess, details) in cases {
+            let err = api.guess(&guess_payload(&session_id, guess)).unwrap_err();
+            assert_eq!(err.id(), errors::HARD_MODE_ERROR, "{}", guess);
+            assert_eq!(err.details(), Some(details), "{}", guess);
+        }
+        assert!(api.guess(&guess_payload(&session_id, "abbey")).is_ok());
+
+        let context = Context(XSpanIdString::default());
+        let response =
+            wurdle_openapi::Api::do_guess(&api, guess_payload(&session_id, "kebab"), &context)
+                .await
+                .unwrap();
+        match response {
+            wurdle_openapi::DoGuessResponse::InvalidGuess(err) => {
+                assert_eq!(err.id, errors::HARD_MODE_ERROR)
+            }
+            _ => panic!("expected an invalid guess"),
+        }
+
+        // Without hard mode, anything in the lists can be guessed
+        let start = api.word_session(&payload(false)).unwrap();
+        let session_id = api
+            .guess(&guess_payload(&start.session_id, "babes"))
+            .unwrap()
+            .session_id;
+        assert!(api.guess(&guess_payload(&session_id, "tabes")).is_ok());
+    }
+
+    #[tokio::test]
+    async fn encrypted_sessions_hide_the_word_id() {
+        let signed = api().await;
+        let start = signed.word_session(&word_payload("crane")).unwrap();
+        assert!(start.word_id.is_some());
+        let info = signed.session_info(&start.session_id).unwrap();
+        assert_eq!(info.word_id, start.word_id);
+
+        let api = api_with_mode(Mode::Encrypted).await;
+        let starts = [
+            api.word_session(&word_payload("crane")).unwrap(),
+            api.random_session(&random_payload(None, Some(1))).unwrap(),
+            api.id_session(&id_payload(start.word_id.as_deref().unwrap()))
+                .unwrap(),
+        ];
+        for start in starts {
+            assert_eq!(start.word_id, None);
+            let reply = api
+                .guess(&guess_payload(&start.session_id, "sloth"))
+                .unwrap();
+            for session_id in [&start.session_id, &reply.session_id] {
+                assert_eq!(api.session_info(session_id).unwrap().word_id, None);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn ids_are_resolved_whatever_their_length() {
+        let api = api().await;
+        let word = api.db(None).unwrap().word_exists("crayon").unwrap();
+        let start = api.id_session(&id_payload(&word.word_id)).unwrap();
+        let session = api.get_session(&start.session_id).unwrap();
+        assert_eq!(session.word_length, 6);
+        assert_eq!(session.word_id, word.word_id);
+
+        let payload = |word_length| models::StartWithIdPayload {
+            word_length: Some(word_length),
+            ..id_payload(&word.word_id)
+        };
+        assert!(api.id_session(&payload(6)).is_ok());
+        assert!(matches!(
+            api.id_session(&payload(5)),
+            Err(errors::Error::InvalidID { .. })
+        ));
+        assert!(matches!(
+            api.id_session(&payload(9)),
+            Err(errors::Error::UnsupportedWordLength { length: 9 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn finished_sessions_are_reported_first() {
+        let api = api().await;
+        let start = api.word_session(&word_payload("crane")).unwrap();
+        let finished = api
+            .guess(&guess_payload(&start.session_id, "crane"))
+            .unwrap()
+            .session_id;
+        // Too short, not in the lists, not letters, and valid
+        for guess in ["cran", "zzzzz", "cr4ne", "sloth"] {
+            assert!(
+                matches!(
+                    api.guess(&guess_payload(&finished, guess)),
+                    Err(errors::Error::SessionFinished)
+                ),
+                "{}",
+                guess
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn max_guesses_must_be_in_bounds() {
+        assert_eq!(
+            parse_max_guesses(None).unwrap(),
+            game::game::DEFAULT_MAX_GUESSES
+        );
+        for max_guesses in [1, 4, 10, 20] {
+            assert_eq!(
+                parse_max_guesses(Some(max_guesses)).unwrap(),
+                max_guesses as u8
+            );
+        }
+
+        let api = api().await;
+        for max_guesses in [-1, 0, 21, 256, i32::MAX] {
+            let payload = models::StartWithWordPayload {
+                max_guesses: Some(max_guesses),
+                ..word_payload("crane")
+            };
+            let err = api.word_session(&payload).unwrap_err();
+            assert_eq!(
+                err.id(),
+                errors::INVALID_MAX_GUESSES_ERROR,
+                "{}",
+                max_guesses
+            );
+            assert_eq!(
+                err.details(),
+                Some(json!({ "max_guesses": max_guesses })),
+                "{}",
+                max_guesses
+            );
+        }
+
+        // The session ends after the configured number of guesses
+        let payload = models::StartWithWordPayload {
+            max_guesses: Some(4),
+            ..word_payload("crane")
+        };
+        let mut session_id = api.word_session(&payload).unwrap().session_id;
+        for guess_number in 1..=4 {
+            let reply = api.guess(&guess_payload(&session_id, "sloth")).unwrap();
+            assert_eq!(reply.guess_number, f64::from(guess_number));
+            let expected = if guess_number < 4 {
+                "in_progress"
+            } else {
+                "failed"
+            };
+            assert_eq!(reply.status, expected);
+            session_id = reply.session_id;
+        }
+        assert_eq!(api.session_info(&session_id).unwrap().max_guesses, 4);
+    }
+
+    fn daily_payload(date: Option<String>, utc_offset: Option<i32>) -> models::StartDailyPayload {
+        models::StartDailyPayload {
+            language: None,
+            date,
+            utc_offset,
+            word_length: None,
+            hard_mode: None,
+            max_guesses: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn future_puzzles_are_refused() {
+        let api = api().await;
+        let now = session::session::now();
+        // The furthest time zones are always at least a day apart
+        let earliest = game::daily::Date::today(now, -12 * 60).unwrap();
+        let latest = game::daily::Date::today(now, 14 * 60).unwrap();
+
+        let start = api
+            .daily_session(&daily_payload(Some(latest.to_string()), Some(14 * 60)))
+            .unwrap();
+        assert_eq!(
+            start.puzzle_number,
+            Some(latest.puzzle_number().unwrap().into())
+        );
+        let start = api
+            .daily_session(&daily_payload(None, Some(-12 * 60)))
+            .unwrap();
+        assert_eq!(
+            start.puzzle_number,
+            Some(earliest.puzzle_number().unwrap().into())
+        );
+
+        let err = api
+            .daily_session(&daily_payload(Some(latest.to_string()), Some(-12 * 60)))
+            .unwrap_err();
+        assert_eq!(err.id(), errors::FUTURE_DATE_ERROR);
+        assert_eq!(err.details(), Some(json!({ "date": latest.to_string() })));
+        assert!(matches!(
+            api.daily_session(&daily_payload(Some("2999-01-01".to_string()), None)),
+            Err(errors::Error::FutureDate { .. })
+        ));
+
+        // Past puzzles can still be played, dates before the first one have no puzzle
+        let start = api
+            .daily_session(&daily_payload(Some("2022-01-01".to_string()), None))
+            .unwrap();
+        assert_eq!(start.puzzle_number, Some(0));
+        for date in ["2021-12-31", "2022-02-29"] {
+            assert!(matches!(
+                api.daily_session(&daily_payload(Some(date.to_string()), None)),
+                Err(errors::Error::InvalidDate { .. })
+            ));
+        }
+        assert!(matches!(
+            api.daily_session(&daily_payload(None, Some(15 * 60))),
+            Err(errors::Error::InvalidUtcOffset { utc_offset: 900 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn packs_are_listed_with_their_word_counts() {
+        let api = api().await;
+        let pools = api.db(None).unwrap().pools().unwrap();
+        let listed = pools
+            .iter()
+            .map(|pool| {
+                (
+                    pool.name.as_str(),
+                    pool.word_counts
+                        .iter()
+                        .map(|(length, count)| (*length, *count))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listed,
+            [
+                (database::pools::FULL_POOL, vec![(5, 20), (6, 2)]),
+                (database::pools::RESTRICTED_POOL, vec![(5, 5), (6, 2)]),
+                ("animals", vec![(5, 1), (6, 1)]),
+            ]
+        );
+        assert_eq!(pools[2].description, "Animals");
+
+        let context = Context(XSpanIdString::default());
+        let response = wurdle_openapi::Api::get_packs(&api, None, &context)
+            .await
+            .unwrap();
+        let packs = match response {
+            wurdle_openapi::GetPacksResponse::SuccessfulOperation(list) => list.packs,
+            _ => panic!("expected the list of packs"),
+        };
+        assert_eq!(packs.len(), 3);
+        assert_eq!(packs[0].name, database::pools::FULL_POOL);
+        assert_eq!(packs[0].description, database::pools::FULL_DESCRIPTION);
+        assert_eq!(packs[0].word_counts.len(), 2);
+        assert_eq!(packs[0].word_counts[1].word_length, 6);
+        assert_eq!(packs[0].word_counts[1].count, 2);
+        assert_eq!(packs[2].name, "animals");
+
+        let response = wurdle_openapi::Api::get_packs(&api, Some("xx".to_string()), &context)
+            .await
+            .unwrap();
+        match response {
+            wurdle_openapi::GetPacksResponse::InvalidParameters(err) => {
+                assert_eq!(err.id, errors::UNKNOWN_LANGUAGE_ERROR)
+            }
+            _ => panic!("expected invalid parameters"),
+        }
+    }
+
+    // The default api with a French list which ignores accents
+    async fn api_with_french() -> Api<ListDatabase> {
+        let mut api = api().await;
+        let language = Language {
+            code: "fr".to_string(),
+            answers: vec![Source::Embedded {
+                name: "answers",
+                body: "élan\ncafé\n",
+            }],
+            allowed: vec![Source::Embedded {
+                name: "allowed",
+                body: "crème\n",
+            }],
+            fold_accents: true,
+        };
+        let ids = WordIds::new(b"test secret").unwrap();
+        let fetcher = Fetcher::new(Duration::from_secs(1), 0, Duration::ZERO).unwrap();
+        let db = ListDatabase::new(&language, &[], &ids, &fetcher)
+            .await
+            .unwrap();
+        api.databases.insert(language.code, db);
+        api
+    }
+
+    #[tokio::test]
+    async fn languages_are_listed() {
+        let api = api_with_french().await;
+        let context = Context(XSpanIdString::default());
+        let languages = match wurdle_openapi::Api::get_languages(&api, &context)
+            .await
+            .unwrap()
+        {
+            wurdle_openapi::GetLanguagesResponse::SuccessfulOperation(list) => list.languages,
+            _ => panic!("expected the list of languages"),
+        };
+        let listed = languages
+            .iter()
+            .map(|language| {
+                (
+                    language.code.as_str(),
+                    language.fold_accents,
+                    language.word_lengths.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(listed, [("en", false, vec![5, 6]), ("fr", true, vec![4])]);
+    }
+
+    #[tokio::test]
+    async fn accents_are_ignored_in_folded_languages() {
+        let api = api_with_french().await;
+        let payload = models::StartWithWordPayload {
+            language: Some("fr".to_string()),
+            ..word_payload("Élan")
+        };
+        let start = api.word_session(&payload).unwrap();
+        let session = api.get_session(&start.session_id).unwrap();
+        assert_eq!(session.language.as_deref(), Some("fr"));
+        assert_eq!(session.word_length, 4);
+
+        let reply = api
+            .guess(&guess_payload(&start.session_id, "CAFÉ"))
+            .unwrap();
+        assert_eq!(reply.status, "in_progress");
+        let reply = api
+            .guess(&guess_payload(&reply.session_id, "elan"))
+            .unwrap();
+        assert_eq!(reply.status, "guessed");
+        let info = api.session_info(&reply.session_id).unwrap();
+        assert_eq!(info.language, "fr");
+        assert_eq!(info.board[0].guess, "cafe");
+
+        // English words are not French words
+        assert!(matches!(
+            api.guess(&guess_payload(&start.session_id, "crane")),
+            Err(errors::Error::WrongLength { .. })
+        ));
+        let start = api.word_session(&payload).unwrap();
+        let err = api
+            .guess(&guess_payload(&start.session_id, "zinc"))
+            .unwrap_err();
+        assert_eq!(err.id(), errors::NOT_IN_GUESS_LIST_ERROR);
+        assert!(matches!(
+            api.word_session(&models::StartWithWordPayload {
+                language: Some("de".to_string()),
+                ..word_payload("elan")
+            }),
+            Err(errors::Error::UnknownLanguage { .. })
+        ));
+    }
 }
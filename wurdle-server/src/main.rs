@@ -1,12 +1,19 @@
 #![deny(warnings)]
 
 mod database;
+mod game;
 mod http;
 mod session;
 
 use database::http as db;
-use log::{debug, info};
+use database::languages::{self, Language};
+use database::pools::{self, Pool};
+use database::{embedded, source::Source};
+use log::{debug, error, info, warn};
+use std::collections::BTreeMap;
 use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -21,23 +28,218 @@ enum Error {
     Env(#[from] env::VarError),
     #[error(transparent)]
     Parse(#[from] std::num::ParseIntError),
+    #[error(transparent)]
+    Base64(#[from] base64::DecodeError),
+    #[error("WORD_ID_SECRET must be set when several session keys are configured")]
+    WordIdSecret,
 }
 
 const ENV_SESSION_TOKEN: &str = "SESSION_TOKEN";
+const ENV_SESSION_KEYS: &str = "SESSION_KEYS";
+const ENV_SESSION_KEYS_FILE: &str = "SESSION_KEYS_FILE";
+const ENV_SESSION_TTL: &str = "SESSION_TTL";
+const ENV_SESSION_MODE: &str = "SESSION_MODE";
+const ENV_WORD_ID_SECRET: &str = "WORD_ID_SECRET";
 const ENV_PORT: &str = "PORT";
 const DEFAULT_PORT: u16 = 8888;
 const ENV_PUBLIC_SERVER: &str = "PUBLIC_SERVER";
+const ENV_ANSWERS_FILE: &str = "ANSWERS_FILE";
+const ENV_ALLOWED_WORDS_FILE: &str = "ALLOWED_WORDS_FILE";
+const ENV_ANSWERS_URL: &str = "ANSWERS_URL";
+const ENV_ALLOWED_WORDS_URL: &str = "ALLOWED_WORDS_URL";
+const ENV_DOWNLOAD_TIMEOUT: &str = "DOWNLOAD_TIMEOUT";
+const DEFAULT_DOWNLOAD_TIMEOUT: u64 = 10;
+const ENV_DOWNLOAD_RETRIES: &str = "DOWNLOAD_RETRIES";
+const DEFAULT_DOWNLOAD_RETRIES: u32 = 3;
+const DOWNLOAD_BACKOFF: Duration = Duration::from_millis(500);
+const ENV_DAILY_SEED: &str = "DAILY_SEED";
+const DEFAULT_DAILY_SEED: &str = "wurdle daily";
+const ENV_RESTRICTED_POOL_SIZE: &str = "RESTRICTED_POOL_SIZE";
+const ENV_RESTRICTED_POOL_SEED: &str = "RESTRICTED_POOL_SEED";
+const ENV_ANSWER_POOLS: &str = "ANSWER_POOLS";
+const ENV_LANGUAGES: &str = "LANGUAGES";
+const ENV_FOLD_ACCENTS: &str = "FOLD_ACCENTS";
+
+// Configured local file (or remote URL, which can be changed, by default), then the lists built
+// into the binary
+fn words_sources(
+    file_variable: &str,
+    url_variable: &str,
+    default_url: &str,
+    embedded_name: &'static str,
+    embedded_body: &'static str,
+) -> Vec<Source> {
+    let primary = match env::var(file_variable).ok() {
+        Some(path) => Source::File {
+            path: PathBuf::from(path),
+        },
+        None => Source::Http {
+            url: env::var(url_variable).unwrap_or_else(|_e| default_url.to_string()),
+        },
+    };
+    vec![
+        primary,
+        Source::Embedded {
+            name: embedded_name,
+            body: embedded_body,
+        },
+    ]
+}
+
+// The sampled restricted pool, then the curated pools which can replace it
+fn answer_pools() -> Result<Vec<Pool>, Error> {
+    let size = match env::var(ENV_RESTRICTED_POOL_SIZE).ok() {
+        Some(size) => size.parse::<usize>()?,
+        None => pools::DEFAULT_RESTRICTED_SIZE,
+    };
+    let seed = env::var(ENV_RESTRICTED_POOL_SEED)
+        .unwrap_or_else(|_e| pools::DEFAULT_RESTRICTED_SEED.to_string());
+    let mut answer_pools = vec![Pool::Sample {
+        name: pools::RESTRICTED_POOL.to_string(),
+        description: format!("Random sample of {} answers of each length", size),
+        size,
+        seed,
+    }];
+    if let Ok(list) = env::var(ENV_ANSWER_POOLS) {
+        answer_pools.extend(pools::parse_pools(&list)?);
+    }
+    Ok(answer_pools)
+}
+
+// The default language, then the ones configured from local files
+fn configured_languages() -> Result<Vec<Language>, Error> {
+    let mut configured = vec![Language {
+        code: languages::DEFAULT_LANGUAGE.to_string(),
+        answers: words_sources(
+            ENV_ANSWERS_FILE,
+            ENV_ANSWERS_URL,
+            db::ANSWERS,
+            "answers",
+            embedded::ANSWERS,
+        ),
+        allowed: words_sources(
+            ENV_ALLOWED_WORDS_FILE,
+            ENV_ALLOWED_WORDS_URL,
+            db::ALLOWED_WORDS,
+            "allowed",
+            embedded::ALLOWED_WORDS,
+        ),
+        fold_accents: false,
+    }];
+    if let Ok(list) = env::var(ENV_LANGUAGES) {
+        configured.extend(languages::parse_languages(&list)?);
+    }
+    let folded = env::var(ENV_FOLD_ACCENTS).unwrap_or_default();
+    let folded = folded.split(',').map(str::trim).collect::<Vec<_>>();
+    for language in configured.iter_mut() {
+        language.fold_accents = folded.contains(&language.code.as_str());
+    }
+    Ok(configured)
+}
+
+fn fetcher() -> Result<db::Fetcher, Error> {
+    let timeout = match env::var(ENV_DOWNLOAD_TIMEOUT).ok() {
+        Some(timeout) => timeout.parse::<u64>()?,
+        None => DEFAULT_DOWNLOAD_TIMEOUT,
+    };
+    let retries = match env::var(ENV_DOWNLOAD_RETRIES).ok() {
+        Some(retries) => retries.parse::<u32>()?,
+        None => DEFAULT_DOWNLOAD_RETRIES,
+    };
+    Ok(db::Fetcher::new(
+        Duration::from_secs(timeout),
+        retries,
+        DOWNLOAD_BACKOFF,
+    )?)
+}
+
+// All languages are loaded at the same time
+async fn load_databases(
+    word_ids: &database::ids::WordIds,
+    fetcher: &db::Fetcher,
+) -> Result<BTreeMap<String, db::Database>, Error> {
+    let pools = answer_pools()?;
+    let configured = configured_languages()?;
+    let databases = futures::future::try_join_all(configured.iter().map(|language| {
+        // Curated pools are lists of words of the default language
+        let pools = match language.code.as_str() {
+            languages::DEFAULT_LANGUAGE => &pools[..],
+            _ => &pools[..1],
+        };
+        debug!("creating database for {}", language.code);
+        db::Database::new(language, pools, word_ids, fetcher)
+    }))
+    .await?;
+    Ok(configured
+        .into_iter()
+        .map(|language| language.code)
+        .zip(databases)
+        .collect())
+}
+
+// In order of priority: a file containing keys, a list of keys or a single key
+fn session_keys() -> Result<Vec<session::keys::Key>, Error> {
+    if let Ok(path) = env::var(ENV_SESSION_KEYS_FILE) {
+        return Ok(session::keys::keys_from_file(&PathBuf::from(path))?);
+    }
+    if let Ok(keys) = env::var(ENV_SESSION_KEYS) {
+        return Ok(session::keys::parse_keys(&keys)?);
+    }
+    let token = env::var(ENV_SESSION_TOKEN)?;
+    Ok(vec![session::keys::Key::new(
+        session::keys::DEFAULT_KEY_ID,
+        &token,
+    )?])
+}
+
+// Session keys can be rotated but word ids must not change, they use their own secret. A single
+// session key is used by default, with more keys there is no telling which one will stay
+fn word_ids(keys: &[session::keys::Key]) -> Result<database::ids::WordIds, Error> {
+    let secret = match (env::var(ENV_WORD_ID_SECRET).ok(), keys) {
+        (Some(secret), _) => base64::decode(secret)?,
+        (None, [key]) => key.secret.clone(),
+        (None, _) => return Err(Error::WordIdSecret),
+    };
+    Ok(database::ids::WordIds::new(&secret)?)
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     env_logger::init();
 
-    debug!("creating database");
-    let db = db::Database::new()?;
+    let keys = session_keys()?;
+    let word_ids = word_ids(&keys)?;
+    let databases = match load_databases(&word_ids, &fetcher()?).await {
+        Ok(databases) => databases,
+        Err(err) => {
+            error!(
+                "could not load the word lists, not starting the server: {}",
+                err
+            );
+            return Err(err);
+        }
+    };
 
-    let token = env::var(ENV_SESSION_TOKEN)?;
-    debug!("create session manager");
-    let sessions = session::manager::SessionManager::new(&token)?;
+    let ttl = match env::var(ENV_SESSION_TTL).ok() {
+        Some(ttl) => Some(Duration::from_secs(ttl.parse::<u64>()?)),
+        None => None,
+    };
+    let mode = match env::var(ENV_SESSION_MODE).ok() {
+        Some(mode) => mode.parse::<session::manager::Mode>()?,
+        None => session::manager::Mode::Signed,
+    };
+    debug!("create session manager (mode={:?})", mode);
+    let sessions = session::manager::SessionManager::new(&keys, mode, ttl)?;
+
+    // The default seed is public, future puzzles would not be secret
+    let daily_seed = env::var(ENV_DAILY_SEED).unwrap_or_else(|_e| {
+        warn!(
+            "{} is not set, anyone can compute the word of every future daily puzzle",
+            ENV_DAILY_SEED
+        );
+        DEFAULT_DAILY_SEED.to_string()
+    });
+    let daily = game::daily::Daily::new(&daily_seed);
 
     let port = env::var(ENV_PORT).ok();
     let port = match port {
@@ -50,7 +252,7 @@ async fn main() -> Result<(), Error> {
         None => true,
     };
     info!("running server locally={} with port {}", local, port);
-    http::server::run(db, sessions, local, port).await?;
+    http::server::run(databases, sessions, daily, local, port).await?;
     debug!("server stopped");
 
     Ok(())
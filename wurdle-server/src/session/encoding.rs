@@ -0,0 +1,239 @@
+// Minimal binary encoding primitives used to keep sessions (and thus tokens) short
+use super::traits;
+
+#[derive(Default)]
+pub struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn byte(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    // LEB128: 7 bits per byte, the high bit is set when more bytes follow
+    pub fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.data.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.data.push(value as u8);
+    }
+
+    pub fn bytes(&mut self, value: &[u8]) {
+        self.varint(value.len() as u64);
+        self.data.extend_from_slice(value);
+    }
+
+    pub fn string(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    // Packs values of `bits` bits each (at most 8), prefixed by their count
+    pub fn packed(&mut self, values: &[u8], bits: u8) {
+        self.varint(values.len() as u64);
+        let mut packed = vec![0u8; (values.len() * bits as usize).div_ceil(8)];
+        for (index, value) in values.iter().enumerate() {
+            let offset = index * bits as usize;
+            let wide = (*value as u16) << (offset % 8);
+            packed[offset / 8] |= wide as u8;
+            if let Some(next) = packed.get_mut(offset / 8 + 1) {
+                *next |= (wide >> 8) as u8;
+            }
+        }
+        self.data.extend(packed);
+    }
+}
+
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], traits::Error> {
+        if self.data.len() < length {
+            return Err(traits::Error::InvalidFormatting);
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    pub fn byte(&mut self) -> Result<u8, traits::Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    // Only the shortest encoding of a value is accepted
+    pub fn varint(&mut self) -> Result<u64, traits::Error> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as u64;
+            if bits << shift >> shift != bits {
+                return Err(traits::Error::InvalidFormatting);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                if byte == 0 && shift > 0 {
+                    return Err(traits::Error::InvalidFormatting);
+                }
+                return Ok(value);
+            }
+        }
+        Err(traits::Error::InvalidFormatting)
+    }
+
+    pub fn varint_u32(&mut self) -> Result<u32, traits::Error> {
+        u32::try_from(self.varint()?).map_err(|_e| traits::Error::InvalidFormatting)
+    }
+
+    fn length(&mut self) -> Result<usize, traits::Error> {
+        usize::try_from(self.varint()?).map_err(|_e| traits::Error::InvalidFormatting)
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], traits::Error> {
+        let length = self.length()?;
+        self.take(length)
+    }
+
+    pub fn string(&mut self) -> Result<String, traits::Error> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_e| traits::Error::InvalidFormatting)
+    }
+
+    pub fn packed(&mut self, bits: u8) -> Result<Vec<u8>, traits::Error> {
+        let count = self.length()?;
+        let packed = self.take(count.saturating_mul(bits as usize).div_ceil(8))?;
+        let mask = ((1u16 << bits) - 1) as u8;
+        Ok((0..count)
+            .map(|index| {
+                let offset = index * bits as usize;
+                let mut wide = packed[offset / 8] as u16;
+                if let Some(next) = packed.get(offset / 8 + 1) {
+                    wide |= (*next as u16) << 8;
+                }
+                (wide >> (offset % 8)) as u8 & mask
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(data: &[u8]) -> Result<u64, traits::Error> {
+        let mut reader = Reader::new(data);
+        let value = reader.varint()?;
+        assert!(reader.is_empty());
+        Ok(value)
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        let cases: [(u64, usize); 8] = [
+            (0, 1),
+            (1, 1),
+            (127, 1),
+            (128, 2),
+            (300, 2),
+            (u32::MAX as u64, 5),
+            (u64::MAX >> 1, 9),
+            (u64::MAX, 10),
+        ];
+        for (value, length) in cases {
+            let mut writer = Writer::new();
+            writer.varint(value);
+            let data = writer.finish();
+            assert_eq!(data.len(), length, "{}", value);
+            assert_eq!(varint(&data).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn varint_invalid() {
+        let cases: [&[u8]; 6] = [
+            // Truncated
+            &[],
+            &[0x80],
+            &[0xff, 0xff],
+            // Overlong
+            &[0x80, 0x00],
+            &[0xff, 0x80, 0x00],
+            &[0x80; 11],
+        ];
+        for data in cases {
+            assert!(varint(data).is_err(), "{:?}", data);
+        }
+        // More than 64 bits
+        let mut data = [0xff; 10];
+        data[9] = 0x02;
+        assert!(varint(&data).is_err());
+        data[9] = 0x01;
+        assert_eq!(varint(&data).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn packed_round_trip() {
+        for bits in 1..=8u8 {
+            let mask = ((1u16 << bits) - 1) as u8;
+            for count in 0..20 {
+                let values = (0..count)
+                    .map(|index| (index as u8).wrapping_mul(37) & mask)
+                    .collect::<Vec<_>>();
+                let mut writer = Writer::new();
+                writer.packed(&values, bits);
+                let data = writer.finish();
+                assert_eq!(data.len(), 1 + (count * bits as usize).div_ceil(8));
+
+                let mut reader = Reader::new(&data);
+                assert_eq!(reader.packed(bits).unwrap(), values, "{} bits", bits);
+                assert!(reader.is_empty());
+
+                if count > 0 {
+                    let mut reader = Reader::new(&data[..data.len() - 1]);
+                    assert!(reader.packed(bits).is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        let mut writer = Writer::new();
+        writer.string("");
+        writer.string("écrire");
+        writer.byte(42);
+        let data = writer.finish();
+
+        let mut reader = Reader::new(&data);
+        assert_eq!(reader.string().unwrap(), "");
+        assert_eq!(reader.string().unwrap(), "écrire");
+        assert_eq!(reader.byte().unwrap(), 42);
+        assert!(reader.is_empty());
+        assert!(reader.byte().is_err());
+
+        // Truncated and invalid UTF-8
+        let mut reader = Reader::new(&data[..4]);
+        assert_eq!(reader.string().unwrap(), "");
+        assert!(reader.string().is_err());
+        assert!(Reader::new(&[3, b'a']).string().is_err());
+        assert!(Reader::new(&[0xff]).string().is_err());
+        assert!(Reader::new(&[2, 0xc3, 0x28]).string().is_err());
+    }
+}
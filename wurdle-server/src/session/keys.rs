@@ -0,0 +1,105 @@
+use super::traits;
+use base64::decode;
+use std::fs;
+use std::path::Path;
+
+// Used for keys given without an identifier (e.g. `SESSION_TOKEN`)
+pub const DEFAULT_KEY_ID: &str = "default";
+
+const ID_SEPARATOR: char = ':';
+
+pub struct Key {
+    pub id: String,
+    pub secret: Vec<u8>,
+}
+
+impl Key {
+    pub fn new(id: &str, encoded_secret: &str) -> Result<Self, traits::Error> {
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(traits::Error::InvalidKey {
+                reason: format!("invalid key id {:?}", id),
+            });
+        }
+        let secret = decode(encoded_secret)?;
+        // Anyone could sign sessions with an empty secret
+        if secret.is_empty() {
+            return Err(traits::Error::InvalidKey {
+                reason: format!("empty secret for key {:?}", id),
+            });
+        }
+        Ok(Self {
+            id: id.to_string(),
+            secret,
+        })
+    }
+
+    // Parses `<id>:<base64 secret>`
+    pub fn parse(entry: &str) -> Result<Self, traits::Error> {
+        let (id, encoded_secret) =
+            entry
+                .split_once(ID_SEPARATOR)
+                .ok_or_else(|| traits::Error::InvalidKey {
+                    reason: "expected `<id>:<base64 secret>`".to_string(),
+                })?;
+        Self::new(id.trim(), encoded_secret.trim())
+    }
+}
+
+// Parses a list of keys separated by commas or new lines, ignoring blank lines and comments
+// (starting with `#`), the first key is used for signing
+pub fn parse_keys(list: &str) -> Result<Vec<Key>, traits::Error> {
+    list.split([',', '\n'])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty() && !entry.starts_with('#'))
+        .map(Key::parse)
+        .collect()
+}
+
+pub fn keys_from_file(path: &Path) -> Result<Vec<Key>, traits::Error> {
+    parse_keys(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_are_parsed_in_order() {
+        let list =
+            "# rotated in 2022-05\n new:bmV3 , old:b2xk\n\n# old_2:b2xk\ndefault:ZGVmYXVsdA==\n";
+        let keys = parse_keys(list).unwrap();
+        let ids = keys.iter().map(|key| key.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["new", "old", DEFAULT_KEY_ID]);
+        assert_eq!(keys[0].secret, b"new");
+        assert_eq!(keys[2].secret, b"default");
+        assert!(parse_keys(" \n# nothing\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        for entry in [
+            "bmV3",
+            ":bmV3",
+            "a b:bmV3",
+            "key.2:bmV3",
+            "a:",
+            "a: ",
+            "a:====",
+        ] {
+            assert!(
+                matches!(
+                    parse_keys(entry),
+                    Err(traits::Error::InvalidKey { .. } | traits::Error::Base64(_))
+                ),
+                "{:?}",
+                entry
+            );
+        }
+        // One invalid entry rejects the whole list
+        assert!(parse_keys("new:bmV3,old:").is_err());
+    }
+}
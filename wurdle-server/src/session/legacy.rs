@@ -0,0 +1,53 @@
+// Frozen copies of previous session formats, only used to decode sessions created by older
+// versions of the server and upgrade them to the current `Session`
+use super::session;
+
+// Unversioned JSON, before sessions had a version byte
+pub mod v0 {
+    use super::session;
+    use crate::game::game;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub enum Status {
+        InProgress { used_guesses: u8 },
+        Failed,
+        Won { used_guesses: u8 },
+    }
+
+    #[derive(Deserialize)]
+    pub struct Session {
+        pub word_id: String,
+        pub status: Status,
+    }
+
+    impl From<Status> for session::Status {
+        fn from(status: Status) -> Self {
+            match status {
+                Status::InProgress { used_guesses } => session::Status::InProgress { used_guesses },
+                Status::Failed => session::Status::Failed,
+                Status::Won { used_guesses } => session::Status::Won { used_guesses },
+            }
+        }
+    }
+
+    impl From<Session> for session::Session {
+        fn from(legacy: Session) -> Self {
+            session::Session {
+                word_id: legacy.word_id,
+                word_length: game::DEFAULT_WORD_LENGTH,
+                language: None,
+                list_version: None,
+                status: legacy.status.into(),
+                hard_mode: false,
+                max_guesses: game::DEFAULT_MAX_GUESSES,
+                puzzle_number: None,
+                pool: None,
+                history: vec![],
+                // Unknown, those sessions are expired as soon as a TTL is configured
+                issued_at: 0,
+                expires_at: None,
+            }
+        }
+    }
+}
@@ -1,59 +1,443 @@
+use super::keys;
 use super::session;
 use super::traits;
-use base64::{decode, encode};
-use flate2::write::{ZlibDecoder, ZlibEncoder};
-use flate2::Compression;
-use itertools::Itertools;
-use ring::hmac;
+use flate2::write::ZlibDecoder;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{aead, hkdf, hmac};
+use std::collections::HashMap;
 use std::io::prelude::*;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 const SEPARATOR: &str = ".";
+// Sessions used to be compressed, the binary format is smaller without it
+const ZLIB_HEADER: u8 = 0x78;
+static ENCRYPTION_SALT: &[u8] = b"wurdle";
+static ENCRYPTION_INFO: &[u8] = b"session encryption";
+
+// How new sessions are protected, sessions using either mode are always accepted
+#[derive(Clone, Copy, Debug)]
+pub enum Mode {
+    // `<key id>.<payload>.<HMAC>`, the payload can be read by anyone
+    Signed,
+    // `<key id>.<nonce + encrypted payload + tag>`, the payload is opaque
+    Encrypted,
+}
+
+impl FromStr for Mode {
+    type Err = traits::Error;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "signed" => Ok(Mode::Signed),
+            "encrypted" => Ok(Mode::Encrypted),
+            _ => Err(traits::Error::InvalidMode {
+                mode: mode.to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Key {
+    hmac: hmac::Key,
+    aead: Arc<aead::LessSafeKey>,
+}
+
+impl Key {
+    fn new(secret: &[u8]) -> Result<Self, traits::Error> {
+        // The encryption key needs a fixed length, derive it from the secret instead of using it
+        // directly
+        let aead_key: aead::UnboundKey = hkdf::Salt::new(hkdf::HKDF_SHA256, ENCRYPTION_SALT)
+            .extract(secret)
+            .expand(&[ENCRYPTION_INFO], &aead::CHACHA20_POLY1305)?
+            .into();
+        Ok(Self {
+            hmac: hmac::Key::new(hmac::HMAC_SHA256, secret),
+            aead: Arc::new(aead::LessSafeKey::new(aead_key)),
+        })
+    }
+}
 
 #[derive(Clone)]
 pub struct SessionManager {
-    key: hmac::Key,
+    mode: Mode,
+    // Used to sign new sessions, also part of `keys`
+    signing_key_id: String,
+    // Every key which can be used to verify a session, by id
+    keys: HashMap<String, Key>,
+    ttl: Option<Duration>,
+    rng: SystemRandom,
 }
 
 impl SessionManager {
-    pub fn new(token: &str) -> Result<Self, traits::Error> {
-        let key_value = decode(token)?;
-        let key = hmac::Key::new(hmac::HMAC_SHA256, key_value.as_ref());
-        Ok(Self { key })
+    // The first key is used for signing, the others are only used for verification
+    pub fn new(
+        keys: &[keys::Key],
+        mode: Mode,
+        ttl: Option<Duration>,
+    ) -> Result<Self, traits::Error> {
+        // Timestamps are in seconds, a shorter TTL would create sessions which are already expired
+        if matches!(ttl, Some(ttl) if ttl.as_secs() == 0) {
+            return Err(traits::Error::InvalidTtl);
+        }
+        let signing_key_id = keys.first().ok_or(traits::Error::NoKey)?.id.clone();
+        let mut session_keys = HashMap::new();
+        for key in keys {
+            if session_keys
+                .insert(key.id.clone(), Key::new(key.secret.as_ref())?)
+                .is_some()
+            {
+                return Err(traits::Error::InvalidKey {
+                    reason: format!("duplicate key id {:?}", key.id),
+                });
+            }
+        }
+        Ok(Self {
+            mode,
+            signing_key_id,
+            keys: session_keys,
+            ttl,
+            rng: SystemRandom::new(),
+        })
+    }
+
+    // How new sessions are protected
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    // How long new sessions stay valid, forever if `None`
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    fn key(&self, id: &str) -> Result<&Key, traits::Error> {
+        self.keys
+            .get(id)
+            .ok_or_else(|| traits::Error::UnknownKey { id: id.to_string() })
     }
 
     pub fn serialize(&self, session: &session::Session) -> Result<String, traits::Error> {
-        let serialized = session.serialize()?;
-        let compressed = {
-            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
-            encoder.write_all(serialized.as_bytes())?;
-            encoder.finish()?
+        let serialized = session.serialize();
+
+        let key = self.key(&self.signing_key_id)?;
+        let parts = match self.mode {
+            Mode::Signed => {
+                let tag = hmac::sign(&key.hmac, serialized.as_ref());
+                vec![encode(&serialized), encode(tag.as_ref())]
+            }
+            Mode::Encrypted => vec![encode(self.seal(key, serialized)?)],
         };
 
-        let tag = hmac::sign(&self.key, compressed.as_ref());
+        let mut token = vec![self.signing_key_id.clone()];
+        token.extend(parts);
+        Ok(token.join(SEPARATOR))
+    }
+
+    fn seal(&self, key: &Key, mut data: Vec<u8>) -> Result<Vec<u8>, traits::Error> {
+        let mut nonce = [0; aead::NONCE_LEN];
+        self.rng.fill(&mut nonce)?;
+        key.aead.seal_in_place_append_tag(
+            aead::Nonce::assume_unique_for_key(nonce),
+            aead::Aad::from(self.signing_key_id.as_bytes()),
+            &mut data,
+        )?;
 
-        let encoded = encode(compressed);
-        let encoded_tag = encode(tag.as_ref());
+        let mut sealed = nonce.to_vec();
+        sealed.extend(data);
+        Ok(sealed)
+    }
+
+    fn open(&self, key_id: &str, sealed: &[u8]) -> Result<Vec<u8>, traits::Error> {
+        if sealed.len() < aead::NONCE_LEN {
+            return Err(traits::Error::InvalidFormatting);
+        }
+        let (nonce, data) = sealed.split_at(aead::NONCE_LEN);
+        let nonce = aead::Nonce::try_assume_unique_for_key(nonce)?;
+
+        let mut data = data.to_vec();
+        let opened = self.key(key_id)?.aead.open_in_place(
+            nonce,
+            aead::Aad::from(key_id.as_bytes()),
+            &mut data,
+        )?;
+        Ok(opened.to_vec())
+    }
 
-        Ok(vec![encoded, encoded_tag].join(SEPARATOR))
+    // Sessions created before key ids were introduced don't have one, they are accepted as long
+    // as any of the keys matches
+    fn verify(&self, key_id: Option<&str>, data: &[u8], tag: &[u8]) -> Result<(), traits::Error> {
+        match key_id {
+            Some(id) => Ok(hmac::verify(&self.key(id)?.hmac, data, tag)?),
+            None => match self
+                .keys
+                .values()
+                .any(|key| hmac::verify(&key.hmac, data, tag).is_ok())
+            {
+                true => Ok(()),
+                false => Err(traits::Error::Signing(ring::error::Unspecified)),
+            },
+        }
     }
 
     pub fn deserialize(&self, payload: &str) -> Result<session::Session, traits::Error> {
-        let (encoded, encoded_tag) = payload
-            .splitn(2, SEPARATOR)
-            .collect_tuple()
-            .ok_or(traits::Error::InvalidFormatting)?;
-
-        let compressed = decode(encoded)?;
-        let tag = decode(encoded_tag)?;
-        hmac::verify(&self.key, compressed.as_ref(), tag.as_ref())?;
-
-        let serialized = {
-            let mut decoder = ZlibDecoder::new(Vec::new());
-            decoder.write_all(compressed.as_ref())?;
-            String::from_utf8(decoder.finish()?)?
+        let data = match payload.split(SEPARATOR).collect::<Vec<_>>()[..] {
+            [key_id, encoded, encoded_tag] => {
+                let data = decode(encoded)?;
+                self.verify(Some(key_id), data.as_ref(), decode(encoded_tag)?.as_ref())?;
+                data
+            }
+            // Encrypted sessions and signed sessions without key id both have 2 parts, the
+            // former always start with a known key id
+            [key_id, sealed] if self.keys.contains_key(key_id) => {
+                self.open(key_id, decode(sealed)?.as_ref())?
+            }
+            [encoded, encoded_tag] => {
+                let data = decode(encoded)?;
+                self.verify(None, data.as_ref(), decode(encoded_tag)?.as_ref())?;
+                data
+            }
+            _ => return Err(traits::Error::InvalidFormatting),
         };
-        let session = session::Session::deserialize(serialized.as_str())?;
+
+        let serialized = match data.first() {
+            Some(&ZLIB_HEADER) => {
+                let mut decoder = ZlibDecoder::new(Vec::new());
+                decoder.write_all(data.as_ref())?;
+                decoder.finish()?
+            }
+            _ => data,
+        };
+        let mut session = session::Session::deserialize(serialized.as_ref())?;
+
+        // Sessions issued without an expiry (e.g. before a TTL was configured) get one from when
+        // they were issued, legacy sessions don't know when they were issued so they are expired
+        if let (None, Some(ttl)) = (session.expires_at, self.ttl) {
+            session.expires_at = Some(session.issued_at + ttl.as_secs());
+        }
+        if session.is_expired(session::now()) {
+            return Err(traits::Error::Expired {
+                expires_at: session.expires_at.unwrap_or_default(),
+            });
+        }
 
         Ok(session)
     }
 }
+
+// Tokens end up in URLs, avoid characters which need escaping
+fn encode<T: AsRef<[u8]>>(data: T) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+// Older tokens used the standard alphabet with padding
+fn decode(data: &str) -> Result<Vec<u8>, traits::Error> {
+    base64::decode_config(data, base64::URL_SAFE_NO_PAD)
+        .or_else(|_e| base64::decode_config(data, base64::STANDARD))
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::scoring::LetterResult;
+
+    // Tokens created by each version of the server with the key `test:dGVzdCBzZWNyZXQ=`, they
+    // must never change
+    // v0: `{"word_id":"42","status":{"InProgress":{"used_guesses":3}}}`
+    const GOLDEN_V0: &str = "eJyrVirPL0qJz0xRslIyMVLSUSouSSwpLVayqlbyzAsoyk8vSi0G80qLU1Pi00uBvFQg37i2thYAUCcUkQ==.Vb8en+gFytK6+l+YB1cZP6sj0s/wPIZVEaA5zHZC9qo=";
+    // v1 was never released
+    const GOLDEN_V1: &str = "test.eJxVjcEKwjAQRPFX5pyDFvWQH/EgUoJZNBCbks02lZJ/d2tBcC4LjzezuwU1Zd8HD4tjBwMurgjDLrikYT3C5PuHEDMp7lozeDqtvJIn2JKFFAQuKb9hr9uejt2zG0j3MrHEoqCOU61o5mfw6PyfMa1BuxkEZtGvTunhfNpvMaB5DGp/+SAxtg/6iUAL.d7eG20PXObYmPXWEnsIIpYxu9F8ez5raUeMd2QB5io0=";
+    // v2: same session in both modes
+    const GOLDEN_V2_SIGNED: &str = "test.AgALU205b2JpQkViMlUGBgsCZnIBCAIHCAkDAwZwYXJsZXIGJAgDB8OpY3JpcmUGqgoEgIHkkgYFgK6ZpA8.ZhX2mwZfi0xVqUpZKMLjkMWkkfzNGtUcv4De6dZhqE0";
+    const GOLDEN_V2_ENCRYPTED: &str = "test.zJ4W2BNZx5_HFW78idytGPigSVjVRVDA8MdG3kG_Tx2LqtypBSTMBWmoFow4A9w8l-mn6c3mrqWicV-DkgncXbAOCRAjhrArqlg7HEZqqNnY5bGT_rHCA5pQ";
+
+    fn manager(ttl: Option<Duration>) -> SessionManager {
+        let keys = [keys::Key::new("test", "dGVzdCBzZWNyZXQ=").unwrap()];
+        SessionManager::new(&keys, Mode::Signed, ttl).unwrap()
+    }
+
+    fn token(issued_at: u64) -> String {
+        let mut session = session::Session::new("word", 5, false, 6, None);
+        session.issued_at = issued_at;
+        manager(None).serialize(&session).unwrap()
+    }
+
+    #[test]
+    fn zero_ttl_is_rejected() {
+        let keys = [keys::Key::new("test", "dGVzdCBzZWNyZXQ=").unwrap()];
+        assert!(matches!(
+            SessionManager::new(&keys, Mode::Signed, Some(Duration::ZERO)),
+            Err(traits::Error::InvalidTtl)
+        ));
+    }
+
+    #[test]
+    fn sessions_without_expiry_expire_with_ttl() {
+        let ttl = Some(Duration::from_secs(60));
+        let recent = manager(ttl).deserialize(&token(session::now())).unwrap();
+        assert_eq!(recent.expires_at, Some(recent.issued_at + 60));
+
+        assert!(matches!(
+            manager(ttl).deserialize(&token(session::now() - 120)),
+            Err(traits::Error::Expired { .. })
+        ));
+        assert!(manager(None).deserialize(&token(0)).is_ok());
+        assert!(matches!(
+            manager(ttl).deserialize(&token(0)),
+            Err(traits::Error::Expired { .. })
+        ));
+    }
+
+    fn assert_v2(session: &session::Session) {
+        assert_eq!(session.word_id, "Sm9obiBEb2U");
+        assert_eq!(session.word_length, 6);
+        assert_eq!(session.language.as_deref(), Some("fr"));
+        assert_eq!(session.list_version, Some(3));
+        assert!(matches!(
+            session.status,
+            session::Status::InProgress { used_guesses: 2 }
+        ));
+        assert!(session.hard_mode);
+        assert_eq!(session.max_guesses, 8);
+        assert_eq!(session.puzzle_number, None);
+        assert_eq!(session.pool, None);
+        assert_eq!(session.history.len(), 2);
+        assert_eq!(session.history[0].word, "parler");
+        assert_eq!(
+            session.history[0].result,
+            [
+                LetterResult::Wrong,
+                LetterResult::WrongPlace,
+                LetterResult::Valid,
+                LetterResult::Wrong,
+                LetterResult::Wrong,
+                LetterResult::Valid,
+            ]
+        );
+        assert_eq!(session.history[1].word, "écrire");
+        assert_eq!(session.history[1].result, [LetterResult::Valid; 6]);
+        assert_eq!(session.issued_at, 1650000000);
+        assert_eq!(session.expires_at, Some(4102444800));
+    }
+
+    // Upgraded sessions are written in the current version and read back the same
+    fn assert_upgrades(session: &session::Session) {
+        let token = manager(None).serialize(session).unwrap();
+        let payload = token.split(SEPARATOR).nth(1).unwrap();
+        assert_eq!(decode(payload).unwrap()[0], session::VERSION);
+        let upgraded = manager(None).deserialize(&token).unwrap();
+        assert_eq!(format!("{:?}", upgraded), format!("{:?}", session));
+    }
+
+    #[test]
+    fn golden_v0() {
+        let session = manager(None).deserialize(GOLDEN_V0).unwrap();
+        assert_eq!(session.word_id, "42");
+        assert_eq!(session.word_length, 5);
+        assert_eq!(session.language, None);
+        assert_eq!(session.list_version, None);
+        assert!(matches!(
+            session.status,
+            session::Status::InProgress { used_guesses: 3 }
+        ));
+        assert!(!session.hard_mode);
+        assert_eq!(session.max_guesses, 6);
+        assert!(session.history.is_empty());
+        assert_eq!(session.issued_at, 0);
+        assert_eq!(session.expires_at, None);
+        assert_upgrades(&session);
+    }
+
+    #[test]
+    fn golden_v1() {
+        assert!(matches!(
+            manager(None).deserialize(GOLDEN_V1),
+            Err(traits::Error::UnsupportedVersion { version: 1 })
+        ));
+    }
+
+    #[test]
+    fn golden_v2() {
+        for token in [GOLDEN_V2_SIGNED, GOLDEN_V2_ENCRYPTED] {
+            let session = manager(None).deserialize(token).unwrap();
+            assert_v2(&session);
+            assert_upgrades(&session);
+        }
+        // The current version is written exactly the same way
+        let session = manager(None).deserialize(GOLDEN_V2_SIGNED).unwrap();
+        assert_eq!(manager(None).serialize(&session).unwrap(), GOLDEN_V2_SIGNED);
+    }
+
+    #[test]
+    fn golden_tokens_need_the_key() {
+        let keys = [keys::Key::new("test", "b3RoZXIgc2VjcmV0").unwrap()];
+        let manager = SessionManager::new(&keys, Mode::Signed, None).unwrap();
+        for token in [GOLDEN_V0, GOLDEN_V2_SIGNED, GOLDEN_V2_ENCRYPTED] {
+            assert!(manager.deserialize(token).is_err());
+        }
+    }
+
+    #[test]
+    fn retired_keys_still_verify() {
+        let old = || keys::Key::new("old", "b2xkIHNlY3JldA==").unwrap();
+        let new = || keys::Key::new("new", "bmV3IHNlY3JldA==").unwrap();
+        for mode in [Mode::Signed, Mode::Encrypted] {
+            let before = SessionManager::new(&[old()], mode, None).unwrap();
+            let after = SessionManager::new(&[new(), old()], mode, None).unwrap();
+            let session = session::Session::new("word", 5, false, 6, None);
+
+            let token = before.serialize(&session).unwrap();
+            assert!(token.starts_with("old."));
+            let verified = after.deserialize(&token).unwrap();
+            assert_eq!(verified.word_id, "word");
+
+            // New sessions are signed with the first key only, encrypted sessions of an unknown
+            // key look like legacy signed sessions so the error depends on the mode
+            let token = after.serialize(&session).unwrap();
+            assert!(token.starts_with("new."));
+            assert!(before.deserialize(&token).is_err());
+
+            // Once removed, a key verifies nothing
+            let removed = SessionManager::new(&[new()], mode, None).unwrap();
+            assert!(removed
+                .deserialize(&before.serialize(&session).unwrap())
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn key_ids_must_match_their_secret() {
+        let token = manager(None)
+            .serialize(&session::Session::new("word", 5, false, 6, None))
+            .unwrap();
+        // Same id, another secret
+        let keys = [
+            keys::Key::new("new", "bmV3IHNlY3JldA==").unwrap(),
+            keys::Key::new("test", "b3RoZXIgc2VjcmV0").unwrap(),
+        ];
+        let manager = SessionManager::new(&keys, Mode::Signed, None).unwrap();
+        assert!(matches!(
+            manager.deserialize(&token),
+            Err(traits::Error::Signing(_))
+        ));
+    }
+
+    #[test]
+    fn duplicate_key_ids_are_rejected() {
+        let keys = [
+            keys::Key::new("test", "dGVzdCBzZWNyZXQ=").unwrap(),
+            keys::Key::new("test", "b3RoZXIgc2VjcmV0").unwrap(),
+        ];
+        assert!(matches!(
+            SessionManager::new(&keys, Mode::Signed, None),
+            Err(traits::Error::InvalidKey { .. })
+        ));
+        assert!(matches!(
+            SessionManager::new(&[], Mode::Signed, None),
+            Err(traits::Error::NoKey)
+        ));
+    }
+}
@@ -1,3 +1,6 @@
-pub mod traits;
+pub mod encoding;
+pub mod keys;
+pub mod legacy;
 pub mod manager;
 pub mod session;
+pub mod traits;
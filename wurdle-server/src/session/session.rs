@@ -1,7 +1,36 @@
+use super::encoding::{Reader, Writer};
+use super::legacy;
 use super::traits;
-use serde::{Deserialize, Serialize};
+use crate::game::game;
+use crate::game::scoring::LetterResult;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(Serialize, Deserialize, Debug)]
+// Stored as the first byte of a serialized session, bump it (and keep a frozen copy of the
+// previous format in `legacy`) whenever the format changes
+// Version 1 (JSON after the version byte) was never released, so it doesn't need to be decoded
+pub const VERSION: u8 = 2;
+// Sessions created before versioning are plain JSON objects
+const LEGACY_MARKER: u8 = b'{';
+
+// Each field is written as a tag followed by its value, fields with a default value are skipped
+// and new optional fields can be added without a new version
+const TAG_WORD_ID: u8 = 0;
+const TAG_STATUS: u8 = 1;
+const TAG_HARD_MODE: u8 = 2;
+// Repeated, once per guess in order
+const TAG_GUESS: u8 = 3;
+const TAG_ISSUED_AT: u8 = 4;
+const TAG_EXPIRES_AT: u8 = 5;
+const TAG_WORD_LENGTH: u8 = 6;
+const TAG_MAX_GUESSES: u8 = 7;
+const TAG_PUZZLE_NUMBER: u8 = 8;
+const TAG_LIST_VERSION: u8 = 9;
+const TAG_POOL: u8 = 10;
+const TAG_LANGUAGE: u8 = 11;
+
+const RESULT_BITS: u8 = 2;
+
+#[derive(Debug)]
 pub enum Status {
     InProgress { used_guesses: u8 },
     Failed,
@@ -17,27 +46,336 @@ impl Status {
         }
         .to_string()
     }
+
+    // The kind in the lowest 2 bits, the used guesses in the others
+    fn pack(&self) -> u64 {
+        match self {
+            Status::InProgress { used_guesses } => (*used_guesses as u64) << 2,
+            Status::Failed => 1,
+            Status::Won { used_guesses } => (*used_guesses as u64) << 2 | 2,
+        }
+    }
+
+    fn unpack(packed: u64) -> Result<Self, traits::Error> {
+        let used_guesses =
+            u8::try_from(packed >> 2).map_err(|_e| traits::Error::InvalidFormatting)?;
+        match packed & 0b11 {
+            0 => Ok(Status::InProgress { used_guesses }),
+            1 => Ok(Status::Failed),
+            2 => Ok(Status::Won { used_guesses }),
+            _ => Err(traits::Error::InvalidFormatting),
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug)]
+pub struct Guess {
+    pub word: String,
+    pub result: Vec<LetterResult>,
+}
+
+#[derive(Debug)]
 pub struct Session {
     pub word_id: String,
+    // The word id is only meaningful within the list of words of that length
+    pub word_length: u8,
+    // Unset for the default language
+    pub language: Option<String>,
+    // Version of the word list the id comes from, unknown for older sessions
+    pub list_version: Option<u32>,
     pub status: Status,
+    pub hard_mode: bool,
+    pub max_guesses: u8,
+    // Only for daily puzzles
+    pub puzzle_number: Option<u32>,
+    // Only for random sessions, the pool of answers the word was picked from
+    pub pool: Option<String>,
+    pub history: Vec<Guess>,
+    // Both are UNIX timestamps in seconds
+    pub issued_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
 }
 
 impl Session {
-    pub fn new(word_id: &str) -> Self {
+    pub fn new(
+        word_id: &str,
+        word_length: u8,
+        hard_mode: bool,
+        max_guesses: u8,
+        ttl: Option<Duration>,
+    ) -> Self {
+        let issued_at = now();
         Self {
             word_id: word_id.to_string(),
+            word_length,
+            language: None,
+            list_version: None,
+            status: Status::InProgress { used_guesses: 0 },
+            hard_mode,
+            max_guesses,
+            puzzle_number: None,
+            pool: None,
+            history: vec![],
+            issued_at,
+            expires_at: ttl.map(|ttl| issued_at + ttl.as_secs()),
+        }
+    }
+
+    pub fn used_guesses(&self) -> u8 {
+        match self.status {
+            Status::InProgress { used_guesses } | Status::Won { used_guesses } => used_guesses,
+            Status::Failed => self.max_guesses,
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if expires_at <= now)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.byte(VERSION);
+
+        writer.byte(TAG_WORD_ID);
+        writer.string(&self.word_id);
+        if self.word_length != game::DEFAULT_WORD_LENGTH {
+            writer.byte(TAG_WORD_LENGTH);
+            writer.byte(self.word_length);
+        }
+        if let Some(language) = &self.language {
+            writer.byte(TAG_LANGUAGE);
+            writer.string(language);
+        }
+        writer.byte(TAG_STATUS);
+        writer.varint(self.status.pack());
+        if self.hard_mode {
+            writer.byte(TAG_HARD_MODE);
+        }
+        if self.max_guesses != game::DEFAULT_MAX_GUESSES {
+            writer.byte(TAG_MAX_GUESSES);
+            writer.byte(self.max_guesses);
+        }
+        if let Some(list_version) = self.list_version {
+            writer.byte(TAG_LIST_VERSION);
+            writer.varint(list_version.into());
+        }
+        if let Some(puzzle_number) = self.puzzle_number {
+            writer.byte(TAG_PUZZLE_NUMBER);
+            writer.varint(puzzle_number.into());
+        }
+        if let Some(pool) = &self.pool {
+            writer.byte(TAG_POOL);
+            writer.string(pool);
+        }
+        for guess in &self.history {
+            writer.byte(TAG_GUESS);
+            writer.string(&guess.word);
+            writer.packed(
+                &guess
+                    .result
+                    .iter()
+                    .map(|result| result.to_bits())
+                    .collect::<Vec<_>>(),
+                RESULT_BITS,
+            );
+        }
+        if self.issued_at != 0 {
+            writer.byte(TAG_ISSUED_AT);
+            writer.varint(self.issued_at);
+        }
+        if let Some(expires_at) = self.expires_at {
+            writer.byte(TAG_EXPIRES_AT);
+            writer.varint(expires_at);
+        }
+
+        writer.finish()
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<Self, traits::Error> {
+        match data.split_first() {
+            Some((&VERSION, payload)) => Self::decode(payload),
+            Some((&LEGACY_MARKER, _)) => {
+                Ok(serde_json::from_slice::<legacy::v0::Session>(data)?.into())
+            }
+            Some((&version, _)) => Err(traits::Error::UnsupportedVersion { version }),
+            None => Err(traits::Error::InvalidFormatting),
+        }
+    }
+
+    fn decode(payload: &[u8]) -> Result<Self, traits::Error> {
+        let mut reader = Reader::new(payload);
+        let mut word_id = None;
+        let mut session = Self {
+            word_id: String::new(),
+            word_length: game::DEFAULT_WORD_LENGTH,
+            language: None,
+            list_version: None,
             status: Status::InProgress { used_guesses: 0 },
+            hard_mode: false,
+            max_guesses: game::DEFAULT_MAX_GUESSES,
+            puzzle_number: None,
+            pool: None,
+            history: vec![],
+            issued_at: 0,
+            expires_at: None,
+        };
+
+        while !reader.is_empty() {
+            match reader.byte()? {
+                TAG_WORD_ID => word_id = Some(reader.string()?),
+                TAG_WORD_LENGTH => session.word_length = reader.byte()?,
+                TAG_LANGUAGE => session.language = Some(reader.string()?),
+                TAG_STATUS => session.status = Status::unpack(reader.varint()?)?,
+                TAG_HARD_MODE => session.hard_mode = true,
+                TAG_MAX_GUESSES => session.max_guesses = reader.byte()?,
+                TAG_LIST_VERSION => session.list_version = Some(reader.varint_u32()?),
+                TAG_PUZZLE_NUMBER => session.puzzle_number = Some(reader.varint_u32()?),
+                TAG_POOL => session.pool = Some(reader.string()?),
+                TAG_GUESS => session.history.push(Guess {
+                    word: reader.string()?,
+                    result: reader
+                        .packed(RESULT_BITS)?
+                        .into_iter()
+                        .map(|bits| {
+                            LetterResult::from_bits(bits).ok_or(traits::Error::InvalidFormatting)
+                        })
+                        .collect::<Result<_, _>>()?,
+                }),
+                TAG_ISSUED_AT => session.issued_at = reader.varint()?,
+                TAG_EXPIRES_AT => session.expires_at = Some(reader.varint()?),
+                _ => return Err(traits::Error::InvalidFormatting),
+            }
         }
+
+        session.word_id = word_id.ok_or(traits::Error::InvalidFormatting)?;
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{ZlibDecoder, ZlibEncoder};
+    use flate2::Compression;
+    use serde_json::json;
+    use std::io::prelude::*;
+    use std::time::Instant;
+
+    fn sample() -> Session {
+        let mut session = Session::new("Sm9obiBEb2U", 5, true, 6, Some(Duration::from_secs(3600)));
+        session.list_version = Some(3);
+        session.status = Status::Won { used_guesses: 6 };
+        session.history = ["crane", "sloth", "pudgy", "bimbo", "fifty", "wakes"]
+            .iter()
+            .enumerate()
+            .map(|(index, word)| Guess {
+                word: word.to_string(),
+                result: (0..5)
+                    .map(|position| {
+                        LetterResult::from_bits(((index + position) % 3) as u8).unwrap()
+                    })
+                    .collect(),
+            })
+            .collect();
+        session
+    }
+
+    // The format used before the binary encoding: JSON compressed with zlib
+    fn json_zlib(session: &Session) -> Vec<u8> {
+        let history = session
+            .history
+            .iter()
+            .map(|guess| {
+                let result = guess
+                    .result
+                    .iter()
+                    .map(|result| match result {
+                        LetterResult::Valid => 'v',
+                        LetterResult::WrongPlace => 'p',
+                        LetterResult::Wrong => 'w',
+                    })
+                    .collect::<String>();
+                json!({ "word": guess.word, "result": result })
+            })
+            .collect::<Vec<_>>();
+        let serialized = json!({
+            "word_id": session.word_id,
+            "status": { "Won": { "used_guesses": session.used_guesses() } },
+            "hard_mode": session.hard_mode,
+            "history": history,
+            "issued_at": session.issued_at,
+            "expires_at": session.expires_at,
+        });
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, &serialized).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn json_zlib_decode(data: &[u8]) -> serde_json::Value {
+        let mut decoder = ZlibDecoder::new(Vec::new());
+        decoder.write_all(data).unwrap();
+        serde_json::from_slice(&decoder.finish().unwrap()).unwrap()
+    }
+
+    fn encoded_length(data: &[u8]) -> usize {
+        base64::encode_config(data, base64::URL_SAFE_NO_PAD).len()
     }
 
-    pub fn serialize(&self) -> Result<String, traits::Error> {
-        Ok(serde_json::to_string(self)?)
+    #[test]
+    fn round_trip() {
+        let session = sample();
+        let decoded = Session::deserialize(&session.serialize()).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", session));
     }
 
-    pub fn deserialize(data: &str) -> Result<Self, traits::Error> {
-        Ok(serde_json::from_str(data)?)
+    #[test]
+    fn shorter_than_json_zlib() {
+        let session = sample();
+        let binary = encoded_length(&session.serialize());
+        let json = encoded_length(&json_zlib(&session));
+        assert!(binary < json, "binary: {}, JSON+zlib: {}", binary, json);
+    }
+
+    // Run with `make bench`
+    #[test]
+    #[ignore]
+    fn bench_formats() {
+        const ITERATIONS: u32 = 100_000;
+        let session = sample();
+
+        let time = |name: &str, run: &dyn Fn()| {
+            let start = Instant::now();
+            for _ in 0..ITERATIONS {
+                run();
+            }
+            println!("{}: {:?}/iteration", name, start.elapsed() / ITERATIONS);
+        };
+
+        let binary = session.serialize();
+        let json = json_zlib(&session);
+        println!(
+            "token payload: binary {} characters, JSON+zlib {} characters",
+            encoded_length(&binary),
+            encoded_length(&json)
+        );
+        time("binary encode", &|| {
+            session.serialize();
+        });
+        time("binary decode", &|| {
+            Session::deserialize(&binary).unwrap();
+        });
+        time("JSON+zlib encode", &|| {
+            json_zlib(&session);
+        });
+        time("JSON+zlib decode", &|| {
+            json_zlib_decode(&json);
+        });
     }
 }
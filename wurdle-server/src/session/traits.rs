@@ -11,9 +11,21 @@ pub enum Error {
     #[error(transparent)]
     Base64(#[from] base64::DecodeError),
     #[error(transparent)]
-    Utf8(#[from] std::string::FromUtf8Error),
-    #[error(transparent)]
     Signing(#[from] ring::error::Unspecified),
     #[error("invalid format")]
     InvalidFormatting,
+    #[error("unsupported session version: {version}")]
+    UnsupportedVersion { version: u8 },
+    #[error("session expired at {expires_at}")]
+    Expired { expires_at: u64 },
+    #[error("invalid session TTL: must be at least 1 second")]
+    InvalidTtl,
+    #[error("invalid key: {reason}")]
+    InvalidKey { reason: String },
+    #[error("no session key configured")]
+    NoKey,
+    #[error("unknown key: {id}")]
+    UnknownKey { id: String },
+    #[error("invalid session mode: {mode}")]
+    InvalidMode { mode: String },
 }